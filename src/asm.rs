@@ -0,0 +1,755 @@
+// Assembler for the classic CHIP-8 mnemonics, the same ones the disassembler prints
+// with Syntax::Classic. Instructions are checked against the decoder, so whatever
+// assembles here is exactly what the emulator runs.
+//
+//     ; comments start with a semicolon
+//     SPEED   EQU 2
+//     start:  LD V0, SPEED
+//             LD I, ball
+//             DRW V0, V1, 4
+//             JP start
+//     ball:   SPRITE .##., ####, ####, .##.
+//             DB #FF, %10000001, 'A'
+//             INCLUDE "more.s"
+
+use std::{collections::HashMap, error::Error, fmt, fs, path::Path, path::PathBuf};
+
+use crate::{Quirks, Variant, instruction::Instruction};
+
+// What went wrong and where, formatted as file:line: message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+pub struct Assembler {
+    variant: Variant,
+    quirks: Quirks,
+    origin: u16,
+}
+
+impl Assembler {
+    pub fn new() -> Assembler {
+        Assembler {
+            variant: Variant::default(),
+            quirks: Variant::default().default_quirks(),
+            origin: 0x200,
+        }
+    }
+
+    // Instructions outside the variant's set are errors. CHIP-8 by default.
+    // Also picks the variant's usual quirks, like Chip8::with_variant.
+    pub fn with_variant(mut self, variant: Variant) -> Assembler {
+        self.variant = variant;
+        self.quirks = variant.default_quirks();
+        self
+    }
+
+    // The quirks the program will run with. With jump_uses_vx, JP Vx, addr jumps to addr + Vx
+    // and addr has to start with x, otherwise only JP V0, addr exists.
+    pub fn with_quirks(mut self, quirks: Quirks) -> Assembler {
+        self.quirks = quirks;
+        self
+    }
+
+    // Address the image gets loaded at, 0x200 by default.
+    pub fn with_origin(mut self, origin: u16) -> Assembler {
+        self.origin = origin;
+        self
+    }
+
+    // Includes are looked up relative to the current directory.
+    pub fn assemble(&self, source: &str) -> Result<Vec<u8>, AsmError> {
+        let mut lines = Vec::new();
+        read_source(
+            source,
+            "<source>",
+            Path::new(""),
+            &mut Vec::new(),
+            &mut lines,
+        )?;
+        self.assemble_lines(&lines)
+    }
+
+    // Includes are looked up relative to the file including them.
+    pub fn assemble_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, AsmError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let source = fs::read_to_string(path).map_err(|e| AsmError {
+            file: name.clone(),
+            line: 0,
+            message: e.to_string(),
+        })?;
+        let mut lines = Vec::new();
+        let mut including = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+        read_source(
+            &source,
+            &name,
+            path.parent().unwrap_or(Path::new("")),
+            &mut including,
+            &mut lines,
+        )?;
+        self.assemble_lines(&lines)
+    }
+
+    fn assemble_lines(&self, lines: &[SourceLine]) -> Result<Vec<u8>, AsmError> {
+        let statements = lines
+            .iter()
+            .map(|line| parse_line(&line.text).map_err(|message| line.error(message)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // First pass: where everything goes, so labels can be used before they're defined
+        let mut symbols = Symbols::new();
+        let mut address = self.origin as usize;
+        for (line, statement) in lines.iter().zip(&statements) {
+            let fail = |message: String| line.error(message);
+            if let Some(label) = &statement.label {
+                symbols.define(label, address as i64).map_err(fail)?;
+            }
+            match &statement.operation {
+                Operation::Equ(name, value) => {
+                    let value = symbols.evaluate(value).map_err(fail)?;
+                    symbols.define(name, value).map_err(fail)?;
+                }
+                Operation::Org(target) => {
+                    let target = symbols.evaluate(target).map_err(fail)?;
+                    if target < address as i64 {
+                        return Err(fail(format!(
+                            "ORG {:#X} goes back before the current address {:#X}",
+                            target, address
+                        )));
+                    }
+                    address = target as usize;
+                }
+                operation => address += operation.size(),
+            }
+            if address > self.variant.memory_size() {
+                return Err(fail(format!(
+                    "program doesn't fit in memory, it reaches {:#X}",
+                    address
+                )));
+            }
+        }
+
+        // Second pass: the actual bytes
+        let mut image = Vec::new();
+        let mut address = self.origin as usize;
+        for (line, statement) in lines.iter().zip(&statements) {
+            let bytes = match &statement.operation {
+                Operation::Org(target) => {
+                    address = symbols.evaluate(target).map_err(|e| line.error(e))? as usize;
+                    continue;
+                }
+                operation => self
+                    .emit(operation, &symbols)
+                    .map_err(|message| line.error(message))?,
+            };
+            let offset = address - self.origin as usize;
+            if image.len() < offset + bytes.len() {
+                image.resize(offset + bytes.len(), 0);
+            }
+            image[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            address += bytes.len();
+        }
+        Ok(image)
+    }
+
+    fn emit(&self, operation: &Operation, symbols: &Symbols) -> Result<Vec<u8>, String> {
+        match operation {
+            Operation::None | Operation::Equ(..) | Operation::Org(_) => Ok(Vec::new()),
+            Operation::Db(values) => values
+                .iter()
+                .map(|value| byte(symbols.evaluate(value)?))
+                .collect(),
+            Operation::Dw(values) => {
+                let mut bytes = Vec::new();
+                for value in values {
+                    bytes.extend_from_slice(&word(symbols.evaluate(value)?)?.to_be_bytes());
+                }
+                Ok(bytes)
+            }
+            Operation::Sprite(rows) => {
+                let mut bytes = Vec::new();
+                for row in rows {
+                    bytes.extend(sprite_row(row));
+                }
+                Ok(bytes)
+            }
+            Operation::Instruction(mnemonic, operands) => {
+                let (instruction, long) = instruction(mnemonic, operands, symbols, self.quirks)?;
+                // Whatever decodes differently isn't part of this variant
+                let opcode = instruction.encode();
+                if Instruction::decode(opcode, self.variant) != Some(instruction) {
+                    return Err(format!(
+                        "{} isn't part of the {} instruction set",
+                        mnemonic,
+                        variant_name(self.variant)
+                    ));
+                }
+                let mut bytes = opcode.to_be_bytes().to_vec();
+                if let Some(long) = long {
+                    bytes.extend_from_slice(&long.to_be_bytes());
+                }
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+impl Default for Assembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn variant_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Chip8 => "CHIP-8",
+        Variant::SuperChip => "SUPER-CHIP",
+        Variant::XoChip => "XO-CHIP",
+    }
+}
+
+// A line of source after includes were expanded, remembering where it came from.
+struct SourceLine {
+    file: String,
+    number: usize,
+    text: String,
+}
+
+impl SourceLine {
+    fn error(&self, message: String) -> AsmError {
+        AsmError {
+            file: self.file.clone(),
+            line: self.number,
+            message,
+        }
+    }
+}
+
+// Splits source into lines, replacing INCLUDE lines with the included file.
+fn read_source(
+    source: &str,
+    name: &str,
+    directory: &Path,
+    including: &mut Vec<PathBuf>,
+    lines: &mut Vec<SourceLine>,
+) -> Result<(), AsmError> {
+    for (index, text) in source.lines().enumerate() {
+        let line = SourceLine {
+            file: name.to_string(),
+            number: index + 1,
+            text: text.to_string(),
+        };
+        let code = strip_comment(text).trim();
+        let Some(path) = code
+            .split_once(char::is_whitespace)
+            .filter(|(directive, _)| directive.eq_ignore_ascii_case("include"))
+            .map(|(_, path)| path.trim().trim_matches('"'))
+        else {
+            lines.push(line);
+            continue;
+        };
+
+        let path = directory.join(path);
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if including.contains(&canonical) {
+            return Err(line.error(format!("{} includes itself", path.display())));
+        }
+        let included = fs::read_to_string(&path)
+            .map_err(|e| line.error(format!("can't include {}: {}", path.display(), e)))?;
+
+        including.push(canonical);
+        read_source(
+            &included,
+            &path.display().to_string(),
+            path.parent().unwrap_or(Path::new("")),
+            including,
+            lines,
+        )?;
+        including.pop();
+    }
+    Ok(())
+}
+
+// The address in `LD I, LONG address`, the XO-CHIP 16 bit index load.
+fn long_address(operand: &str) -> Option<&str> {
+    let operand = operand.trim();
+    operand
+        .get(..4)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("long"))?;
+    Some(&operand[4..]).filter(|address| !address.is_empty())
+}
+
+// Everything after a semicolon, unless it's inside quotes.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, ';') => return &text[..index],
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    text
+}
+
+struct Statement {
+    label: Option<String>,
+    operation: Operation,
+}
+
+enum Operation {
+    None,
+    Equ(String, String),
+    Org(String),
+    Db(Vec<String>),
+    Dw(Vec<String>),
+    Sprite(Vec<String>),
+    Instruction(String, Vec<String>),
+}
+
+impl Operation {
+    // Bytes this takes in the image. ORG and EQU are handled by the first pass.
+    fn size(&self) -> usize {
+        match self {
+            Operation::None | Operation::Equ(..) | Operation::Org(_) => 0,
+            Operation::Db(values) => values.len(),
+            Operation::Dw(values) => values.len() * 2,
+            Operation::Sprite(rows) => rows.iter().map(|row| row.chars().count().div_ceil(8)).sum(),
+            Operation::Instruction(mnemonic, operands) => {
+                let long = mnemonic == "LD"
+                    && operands
+                        .get(1)
+                        .is_some_and(|operand| long_address(operand).is_some());
+                if long { 4 } else { 2 }
+            }
+        }
+    }
+}
+
+fn parse_line(text: &str) -> Result<Statement, String> {
+    let mut code = strip_comment(text).trim();
+
+    let mut label = None;
+    if let Some((name, rest)) = code.split_once(':')
+        && !name.is_empty()
+        && !name.contains(char::is_whitespace)
+    {
+        check_symbol(name)?;
+        label = Some(name.to_string());
+        code = rest.trim();
+    }
+
+    let (word, rest) = code
+        .split_once(char::is_whitespace)
+        .map_or((code, ""), |(word, rest)| (word, rest.trim()));
+
+    // NAME EQU value
+    if let Some((directive, value)) = rest.split_once(char::is_whitespace)
+        && directive.eq_ignore_ascii_case("equ")
+    {
+        check_symbol(word)?;
+        return Ok(Statement {
+            label,
+            operation: Operation::Equ(word.to_string(), value.trim().to_string()),
+        });
+    }
+
+    let mnemonic = word.to_ascii_uppercase();
+    let operands: Vec<String> = if rest.is_empty() {
+        Vec::new()
+    } else {
+        split_operands(rest)
+    };
+    let operation = match mnemonic.as_str() {
+        "" => Operation::None,
+        "ORG" => Operation::Org(single(&mnemonic, operands)?),
+        "DB" => Operation::Db(expand_chars(operands)?),
+        "DW" => Operation::Dw(operands),
+        "SPRITE" => {
+            let rows: Vec<String> = operands
+                .iter()
+                .map(|row| row.trim_matches('"').to_string())
+                .collect();
+            if let Some(row) = rows
+                .iter()
+                .find(|row| !matches!(row.chars().count(), 1..=8 | 16))
+            {
+                return Err(format!(
+                    "sprite rows are up to 8 or 16 pixels wide, not '{}'",
+                    row
+                ));
+            }
+            Operation::Sprite(rows)
+        }
+        "EQU" => return Err("EQU needs a name before it".to_string()),
+        _ => Operation::Instruction(mnemonic, operands),
+    };
+    Ok(Statement { label, operation })
+}
+
+fn single(mnemonic: &str, mut operands: Vec<String>) -> Result<String, String> {
+    match operands.len() {
+        1 => Ok(operands.remove(0)),
+        _ => Err(format!("{} takes exactly one operand", mnemonic)),
+    }
+}
+
+// Comma separated, quoted text kept whole.
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (None, ',') => operands.push(std::mem::take(&mut current)),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(open), _) if c == open => {
+                quote = None;
+                current.push(c);
+            }
+            _ => current.push(c),
+        }
+    }
+    operands.push(current);
+    operands
+        .into_iter()
+        .map(|operand| operand.trim().to_string())
+        .collect()
+}
+
+// DB "text" is the same as listing the characters one by one.
+fn expand_chars(operands: Vec<String>) -> Result<Vec<String>, String> {
+    let mut values = Vec::new();
+    for operand in operands {
+        match operand.strip_prefix('"') {
+            Some(text) => {
+                let text = text
+                    .strip_suffix('"')
+                    .ok_or_else(|| format!("unterminated string {}", operand))?;
+                values.extend(text.bytes().map(|byte| byte.to_string()));
+            }
+            None => values.push(operand),
+        }
+    }
+    Ok(values)
+}
+
+// One row of pixels: . or 0 is off, anything else is on.
+fn sprite_row(row: &str) -> Vec<u8> {
+    let bits: Vec<bool> = row.chars().map(|c| !matches!(c, '.' | '0')).collect();
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0u8, |byte, (index, &on)| {
+                byte | (u8::from(on) << (7 - index))
+            })
+        })
+        .collect()
+}
+
+const RESERVED: [&str; 11] = [
+    "I", "DT", "ST", "K", "F", "HF", "B", "R", "LONG", "EQU", "ORG",
+];
+
+fn check_symbol(name: &str) -> Result<(), String> {
+    let valid = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("'{}' isn't a valid name", name));
+    }
+    if register(name).is_some() || RESERVED.iter().any(|r| r.eq_ignore_ascii_case(name)) {
+        return Err(format!("'{}' is a reserved name", name));
+    }
+    Ok(())
+}
+
+// Labels and EQU constants, by name.
+struct Symbols(HashMap<String, i64>);
+
+impl Symbols {
+    fn new() -> Symbols {
+        Symbols(HashMap::new())
+    }
+
+    fn define(&mut self, name: &str, value: i64) -> Result<(), String> {
+        if self.0.insert(name.to_string(), value).is_some() {
+            return Err(format!("'{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    // Numbers, names and characters added and subtracted, like `table + 2` or `END-START`.
+    fn evaluate(&self, expression: &str) -> Result<i64, String> {
+        let expression = expression.trim();
+        if expression.is_empty() {
+            return Err("missing value".to_string());
+        }
+
+        let mut total = 0i64;
+        let mut sign = 1;
+        let mut term = String::new();
+        let mut chars = expression.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => {
+                    term.push(c);
+                    term.extend(chars.next());
+                    if let Some(close) = chars.next() {
+                        term.push(close);
+                    }
+                }
+                '+' | '-' if !term.trim().is_empty() => {
+                    total += sign * self.term(&term)?;
+                    term.clear();
+                    sign = if c == '-' { -1 } else { 1 };
+                }
+                '-' => sign = -sign,
+                '+' => {}
+                _ => term.push(c),
+            }
+        }
+        Ok(total + sign * self.term(&term)?)
+    }
+
+    fn term(&self, term: &str) -> Result<i64, String> {
+        let term = term.trim();
+        if let Some(value) = number(term) {
+            return Ok(value);
+        }
+        if let Some(c) = term
+            .strip_prefix('\'')
+            .and_then(|rest| rest.strip_suffix('\''))
+        {
+            let mut chars = c.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                return Ok(c as i64);
+            }
+        }
+        self.0
+            .get(term)
+            .copied()
+            .ok_or_else(|| format!("unknown name or bad number '{}'", term))
+    }
+}
+
+// 42, #2A, $2A, 0x2A, %101010 or 0b101010.
+pub(crate) fn number(text: &str) -> Option<i64> {
+    let (digits, radix) = if let Some(hex) = text.strip_prefix('#').or(text.strip_prefix('$')) {
+        (hex, 16)
+    } else if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(binary) = text.strip_prefix('%').or(text.strip_prefix("0b")) {
+        (binary, 2)
+    } else {
+        (text, 10)
+    };
+    i64::from_str_radix(digits, radix).ok()
+}
+
+pub(crate) fn byte(value: i64) -> Result<u8, String> {
+    match value {
+        0..=255 => Ok(value as u8),
+        -128..=-1 => Ok(value as i8 as u8),
+        _ => Err(format!("{} doesn't fit in a byte", value)),
+    }
+}
+
+pub(crate) fn word(value: i64) -> Result<u16, String> {
+    u16::try_from(value).map_err(|_| format!("{} doesn't fit in 16 bits", value))
+}
+
+pub(crate) fn address(value: i64) -> Result<u16, String> {
+    match value {
+        0..=0xFFF => Ok(value as u16),
+        _ => Err(format!("address {:#X} doesn't fit in 12 bits", value)),
+    }
+}
+
+pub(crate) fn nibble(value: i64) -> Result<u8, String> {
+    match value {
+        0..=15 => Ok(value as u8),
+        _ => Err(format!("{} doesn't fit in 4 bits", value)),
+    }
+}
+
+// V0 to VF, in either case.
+pub(crate) fn register(operand: &str) -> Option<usize> {
+    let digit = operand.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    usize::from_str_radix(digit, 16).ok()
+}
+
+// Operands that aren't values.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    V(usize),
+    I,
+    IndirectI,
+    DT,
+    ST,
+    K,
+    F,
+    HF,
+    B,
+    R,
+    Value,
+}
+
+fn operand_kind(operand: &str) -> Operand {
+    if let Some(x) = register(operand) {
+        return Operand::V(x);
+    }
+    match operand.to_ascii_uppercase().replace(' ', "").as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::DT,
+        "ST" => Operand::ST,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "HF" => Operand::HF,
+        "B" => Operand::B,
+        "R" => Operand::R,
+        _ => Operand::Value,
+    }
+}
+
+const MNEMONICS: [&str; 31] = [
+    "CLS", "RET", "EXIT", "LOW", "HIGH", "SCR", "SCL", "AUDIO", "SCD", "SCU", "PLANE", "PITCH",
+    "JP", "CALL", "SE", "SNE", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SUBN", "SHR", "SHL", "RND",
+    "DRW", "SKP", "SKNP", "SAVE", "LOAD",
+];
+
+// The instruction a mnemonic stands for, plus the address word of LD I, LONG.
+fn instruction(
+    mnemonic: &str,
+    operands: &[String],
+    symbols: &Symbols,
+    quirks: Quirks,
+) -> Result<(Instruction, Option<u16>), String> {
+    use Instruction::*;
+    use Operand::*;
+
+    let kinds: Vec<Operand> = operands
+        .iter()
+        .map(|operand| operand_kind(operand))
+        .collect();
+    let value = |index: usize| symbols.evaluate(&operands[index]);
+    let bad_operands = || {
+        format!(
+            "bad operands for {}: {}",
+            mnemonic,
+            if operands.is_empty() {
+                "none".to_string()
+            } else {
+                operands.join(", ")
+            }
+        )
+    };
+
+    let instruction = match (mnemonic, kinds.as_slice()) {
+        ("CLS", []) => Clear,
+        ("RET", []) => Return,
+        ("EXIT", []) => Exit,
+        ("LOW", []) => LowRes,
+        ("HIGH", []) => HighRes,
+        ("SCR", []) => ScrollRight,
+        ("SCL", []) => ScrollLeft,
+        ("AUDIO", []) => LoadAudioPattern,
+        ("SCD", [Value]) => ScrollDown(nibble(value(0)?)?),
+        ("SCU", [Value]) => ScrollUp(nibble(value(0)?)?),
+        ("PLANE", [Value]) => SelectPlanes(nibble(value(0)?)?),
+        ("PITCH", [V(x)]) => SetPitch(*x),
+        ("JP", [Value]) => Jump(address(value(0)?)?),
+        ("JP", [V(0), Value]) if !quirks.jump_uses_vx => JumpOffset(address(value(1)?)?),
+        // BXNN jumps to XNN + VX
+        ("JP", [V(x), Value]) if quirks.jump_uses_vx => {
+            let nnn = address(value(1)?)?;
+            if usize::from(nnn >> 8) != *x {
+                return Err(format!(
+                    "JP V{:X} can only jump to {:X}00-{:X}FF, not {:#X}",
+                    x, x, x, nnn
+                ));
+            }
+            JumpOffset(nnn)
+        }
+        ("CALL", [Value]) => Call(address(value(0)?)?),
+        ("SE", [V(x), V(y)]) => SEQR(*x, *y),
+        ("SE", [V(x), Value]) => SEQ(*x, byte(value(1)?)?),
+        ("SNE", [V(x), V(y)]) => SNEQR(*x, *y),
+        ("SNE", [V(x), Value]) => SNEQ(*x, byte(value(1)?)?),
+        ("LD", [V(x), V(y)]) => SetRegister(*x, *y),
+        ("LD", [V(x), DT]) => GetDelayTimer(*x),
+        ("LD", [V(x), K]) => GetKey(*x),
+        ("LD", [V(x), IndirectI]) => LoadMemory(*x),
+        ("LD", [V(x), R]) => LoadFlags(*x),
+        ("LD", [V(x), Value]) => Set(*x, byte(value(1)?)?),
+        ("LD", [I, Value]) => {
+            if let Some(address) = long_address(&operands[1]) {
+                let long = word(symbols.evaluate(address)?)?;
+                return Ok((LongIndex, Some(long)));
+            }
+            SetIndex(address(value(1)?)?)
+        }
+        ("LD", [DT, V(x)]) => SetDelayTimer(*x),
+        ("LD", [ST, V(x)]) => SetSoundTimer(*x),
+        ("LD", [F, V(x)]) => GetFontCharacter(*x),
+        ("LD", [HF, V(x)]) => GetBigFontCharacter(*x),
+        ("LD", [B, V(x)]) => BinaryToDecimal(*x),
+        ("LD", [IndirectI, V(x)]) => StoreMemory(*x),
+        ("LD", [R, V(x)]) => StoreFlags(*x),
+        ("ADD", [V(x), V(y)]) => AddRegister(*x, *y),
+        ("ADD", [V(x), Value]) => Add(*x, byte(value(1)?)?),
+        ("ADD", [I, V(x)]) => AddToIndex(*x),
+        ("OR", [V(x), V(y)]) => OR(*x, *y),
+        ("AND", [V(x), V(y)]) => AND(*x, *y),
+        ("XOR", [V(x), V(y)]) => XOR(*x, *y),
+        ("SUB", [V(x), V(y)]) => Subtract(*x, *y),
+        ("SUBN", [V(x), V(y)]) => SubtractInv(*x, *y),
+        ("SHR", [V(x)]) => RShift(*x, *x),
+        ("SHR", [V(x), V(y)]) => RShift(*x, *y),
+        ("SHL", [V(x)]) => LShift(*x, *x),
+        ("SHL", [V(x), V(y)]) => LShift(*x, *y),
+        ("RND", [V(x), Value]) => Random(*x, byte(value(1)?)?),
+        ("DRW", [V(x), V(y), Value]) => Display(*x, *y, nibble(value(2)?)?),
+        ("SKP", [V(x)]) => SkipIfKey(*x),
+        ("SKNP", [V(x)]) => SkipIfNotKey(*x),
+        ("SAVE" | "LOAD", [Value]) => {
+            let (x, y) = operands[0]
+                .split_once('-')
+                .and_then(|(x, y)| Some((register(x.trim())?, register(y.trim())?)))
+                .ok_or_else(bad_operands)?;
+            if mnemonic == "SAVE" {
+                StoreRange(x, y)
+            } else {
+                LoadRange(x, y)
+            }
+        }
+        _ if is_mnemonic(mnemonic) => return Err(bad_operands()),
+        _ => return Err(format!("unknown instruction {}", mnemonic)),
+    };
+    Ok((instruction, None))
+}
+
+fn is_mnemonic(mnemonic: &str) -> bool {
+    MNEMONICS.contains(&mnemonic)
+}
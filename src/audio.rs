@@ -0,0 +1,221 @@
+// Sound output. The core tells a sink when the beeper goes on and off, and
+// on XO-CHIP it also renders the audio pattern into samples, a 60th of a second at a time.
+//
+// Every tick_timers call is one frame: beeper edges and samples come first, then end_frame.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use crate::{Chip8, Variant};
+
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+pub const DEFAULT_TONE: f32 = 440.0;
+pub const DEFAULT_VOLUME: f32 = 0.5;
+
+pub trait AudioSink: Send {
+    // The sound timer started or ran out.
+    fn beeper(&mut self, on: bool);
+
+    // One frame of the XO-CHIP pattern, samples between -1 and 1 at sample_rate.
+    // Only sent while the beeper is on and the program loaded a pattern.
+    fn samples(&mut self, _samples: &[f32]) {}
+
+    // A timer tick went by.
+    fn end_frame(&mut self) {}
+
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_SAMPLE_RATE
+    }
+}
+
+// Throws everything away, for running without sound.
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn beeper(&mut self, _on: bool) {}
+}
+
+// Records the sound to a 16 bit mono PCM WAV file.
+// The beeper is a square wave at the tone frequency, XO-CHIP patterns are written as they are.
+pub struct WavSink<W: Write + Seek + Send> {
+    out: W,
+    sample_rate: u32,
+    tone: f32,
+    volume: f32,
+    beeper: bool,
+    // Position in the square wave, in periods
+    phase: f64,
+    pattern: Vec<f32>,
+    // Sample count, for the header
+    written: u32,
+    // Header and data size need patching
+    dirty: bool,
+}
+
+impl WavSink<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<WavSink<BufWriter<File>>> {
+        WavSink::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek + Send> WavSink<W> {
+    pub fn new(out: W) -> io::Result<WavSink<W>> {
+        let mut sink = WavSink {
+            out,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            tone: DEFAULT_TONE,
+            volume: DEFAULT_VOLUME,
+            beeper: false,
+            phase: 0.0,
+            pattern: Vec::new(),
+            written: 0,
+            dirty: true,
+        };
+        sink.write_header()?;
+        Ok(sink)
+    }
+
+    // Frequency of the beeper, in Hz.
+    pub fn with_tone(mut self, hz: f32) -> WavSink<W> {
+        self.tone = hz.max(1.0);
+        self
+    }
+
+    // From 0 (silent) to 1 (full scale).
+    pub fn with_volume(mut self, volume: f32) -> WavSink<W> {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    // Fixes up the header sizes and flushes. Also done on drop, ignoring errors.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.out.seek(SeekFrom::Start(end))?;
+        self.out.flush()?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let data_size = self.written * 2;
+        let out = &mut self.out;
+        out.write_all(b"RIFF")?;
+        out.write_all(&(36 + data_size).to_le_bytes())?;
+        out.write_all(b"WAVEfmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?; // PCM
+        out.write_all(&1u16.to_le_bytes())?; // Mono
+        out.write_all(&self.sample_rate.to_le_bytes())?;
+        out.write_all(&(self.sample_rate * 2).to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?; // Bytes per frame
+        out.write_all(&16u16.to_le_bytes())?; // Bits per sample
+        out.write_all(b"data")?;
+        out.write_all(&data_size.to_le_bytes())
+    }
+
+    fn write_sample(&mut self, sample: f32) -> io::Result<()> {
+        let value = (sample * self.volume * i16::MAX as f32) as i16;
+        self.written += 1;
+        self.dirty = true;
+        self.out.write_all(&value.to_le_bytes())
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        let length = (self.sample_rate / 60) as usize;
+        let pattern = std::mem::take(&mut self.pattern);
+        let step = self.tone as f64 / self.sample_rate as f64;
+        for n in 0..length {
+            let sample = if let Some(&sample) = pattern.get(n) {
+                sample
+            } else if self.beeper {
+                self.phase = (self.phase + step).fract();
+                if self.phase < 0.5 { 1.0 } else { -1.0 }
+            } else {
+                0.0
+            };
+            self.write_sample(sample)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek + Send> AudioSink for WavSink<W> {
+    fn beeper(&mut self, on: bool) {
+        self.beeper = on;
+    }
+
+    fn samples(&mut self, samples: &[f32]) {
+        self.pattern = samples.to_vec();
+    }
+
+    fn end_frame(&mut self) {
+        let _ = self.write_frame();
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl<W: Write + Seek + Send> Drop for WavSink<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+// Playback rate of the XO-CHIP pattern, in bits per second.
+pub fn pattern_rate(pitch: u8) -> f64 {
+    4000.0 * 2f64.powf((pitch as f64 - 64.0) / 48.0)
+}
+
+impl Chip8 {
+    // Sends sound to an audio sink, one frame every tick_timers.
+    pub fn with_audio_sink<S: AudioSink + 'static>(mut self, sink: S) -> Chip8 {
+        self.audio = Some(Box::new(sink));
+        self
+    }
+
+    // Detaches the audio sink, to finish a recording.
+    pub fn take_audio_sink(&mut self) -> Option<Box<dyn AudioSink>> {
+        self.audio.take()
+    }
+
+    // Whether the sound timer is running, the beeper sounds as long as it's above zero.
+    pub fn beeper(&self) -> bool {
+        self.sound > 0
+    }
+
+    // Feeds the sink the frame that's starting, called by tick_timers before the sound timer goes down.
+    pub(crate) fn play_audio_frame(&mut self) {
+        let Some(sink) = self.audio.as_mut() else {
+            return;
+        };
+        let on = self.sound > 0;
+        if on != self.beeper_on {
+            self.beeper_on = on;
+            sink.beeper(on);
+        }
+        // Without a pattern XO-CHIP programs get the plain beeper too
+        if on && self.variant == Variant::XoChip && self.audio_pattern != [0; 16] {
+            let rate = sink.sample_rate() as f64;
+            let step = pattern_rate(self.pitch) / rate;
+            let mut samples = vec![0.0; (rate as usize) / 60];
+            for sample in samples.iter_mut() {
+                let bit = self.pattern_phase as usize % 128;
+                let lit = self.audio_pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                *sample = if lit { 1.0 } else { -1.0 };
+                self.pattern_phase = (self.pattern_phase + step) % 128.0;
+            }
+            sink.samples(&samples);
+        }
+        sink.end_frame();
+    }
+}
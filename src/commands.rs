@@ -0,0 +1,3 @@
+// Subcommands that work on ROM files instead of running them, like `rust-8 disasm`.
+pub mod asm;
+pub mod disasm;
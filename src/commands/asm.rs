@@ -0,0 +1,80 @@
+use rust_8::{Variant, asm::Assembler, octo::Compiler};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use crate::{is_octo_source, parse_address};
+
+// rust-8 asm <SOURCE> [-o OUT] [--variant NAME] [--entry ADDR]
+// Sources ending in .8o are Octo, anything else uses the classic mnemonics.
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut source_path = None;
+    let mut output_path = None;
+    let mut variant = Variant::default();
+    let mut entry_point = 0x200;
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        match args[i].as_str() {
+            "-o" | "--output" => {
+                output_path = Some(value.ok_or("-o requires an output path")?.clone());
+                i += 2;
+            }
+            "--variant" => {
+                let name = value.ok_or("--variant requires a variant name")?;
+                variant = Variant::from_name(name).ok_or_else(|| {
+                    format!(
+                        "Unknown variant '{}', expected one of: {}",
+                        name,
+                        Variant::NAMES.join(", ")
+                    )
+                })?;
+                i += 2;
+            }
+            "--entry" => {
+                let address = value.ok_or("--entry requires an address")?;
+                entry_point = parse_address(address).ok_or("Invalid entry point address")?;
+                i += 2;
+            }
+            path if !path.starts_with('-') => {
+                source_path = Some(path);
+                i += 1;
+            }
+            flag => return Err(format!("Unknown option '{}' for asm", flag).into()),
+        }
+    }
+
+    let source_path = source_path.ok_or("Usage: rust-8 asm <SOURCE> [-o OUT]")?;
+    // game.s becomes game.ch8 unless told otherwise
+    let output_path = output_path.unwrap_or_else(|| {
+        Path::new(source_path)
+            .with_extension("ch8")
+            .display()
+            .to_string()
+    });
+
+    let result = if is_octo_source(source_path) {
+        Compiler::new()
+            .with_variant(variant)
+            .with_origin(entry_point)
+            .compile_file(source_path)
+    } else {
+        Assembler::new()
+            .with_variant(variant)
+            .with_origin(entry_point)
+            .assemble_file(source_path)
+    };
+    match result {
+        Ok(image) => {
+            fs::write(&output_path, &image)?;
+            println!("Wrote {} bytes to {}", image.len(), output_path);
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
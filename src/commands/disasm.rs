@@ -0,0 +1,78 @@
+use rust_8::{
+    Variant,
+    disasm::{Syntax, disassemble, disassemble_variant},
+};
+use std::error::Error;
+use std::fs;
+
+use crate::parse_address;
+
+// rust-8 disasm <ROM> [--syntax octo|classic] [--variant NAME] [--entry ADDR]
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut rom_path = None;
+    let mut syntax = Syntax::default();
+    let mut variant = None;
+    let mut entry_point = 0x200;
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        match args[i].as_str() {
+            "--syntax" => {
+                let name = value.ok_or("--syntax requires octo or classic")?;
+                syntax = Syntax::from_name(name).ok_or_else(|| {
+                    format!(
+                        "Unknown syntax '{}', expected one of: {}",
+                        name,
+                        Syntax::NAMES.join(", ")
+                    )
+                })?;
+                i += 2;
+            }
+            "--variant" => {
+                let name = value.ok_or("--variant requires a variant name")?;
+                variant = Some(Variant::from_name(name).ok_or_else(|| {
+                    format!(
+                        "Unknown variant '{}', expected one of: {}",
+                        name,
+                        Variant::NAMES.join(", ")
+                    )
+                })?);
+                i += 2;
+            }
+            "--entry" => {
+                let address = value.ok_or("--entry requires an address")?;
+                entry_point = parse_address(address).ok_or("Invalid entry point address")?;
+                i += 2;
+            }
+            path if !path.starts_with("--") => {
+                rom_path = Some(path);
+                i += 1;
+            }
+            flag => return Err(format!("Unknown option '{}' for disasm", flag).into()),
+        }
+    }
+
+    let rom_path = rom_path.ok_or("Usage: rust-8 disasm <ROM> [--syntax octo|classic]")?;
+    let rom = fs::read(rom_path)?;
+    // Without a variant every SUPER-CHIP and XO-CHIP instruction counts as code
+    let lines = match variant {
+        Some(variant) => disassemble_variant(&rom, entry_point, variant),
+        None => disassemble(&rom, entry_point),
+    };
+
+    for line in lines {
+        let bytes: Vec<String> = line
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        println!(
+            "{:04X}  {:<25}{}",
+            line.address,
+            bytes.join(" "),
+            line.mnemonic(syntax)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,354 @@
+// Breakpoints and stepping on top of Chip8::step.
+// The debugger also stands in for the frontend's 60Hz clock, ticking the timers
+// every cycles_per_frame instructions so that programs waiting on them keep going.
+
+use std::{collections::BTreeSet, fmt::Write, ops::Range};
+
+use crate::{Chip8, Chip8Error, DEFAULT_CYCLES_PER_FRAME};
+
+// Why the debugger handed control back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    // Everything that was asked for ran.
+    Done,
+    // The program counter reached a breakpoint. The instruction there hasn't run yet.
+    Breakpoint(u16),
+    // The subroutine being stepped over or out of returned.
+    Returned,
+    // FX0A is waiting for a key, nothing will happen until one is pressed.
+    WaitingForKey,
+    // The ROM executed the SUPER-CHIP exit instruction.
+    Halted,
+    // The instruction limit ran out first.
+    Limit,
+    // The instruction that just ran touched a watchpoint.
+    Watchpoint(WatchHit),
+}
+
+// What a watchpoint keeps an eye on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watchpoint {
+    // Any write to the addresses from start to end included, even one that stores the same value.
+    Memory { start: u16, end: u16 },
+    // V0 to VF taking a different value.
+    Register(usize),
+    // I taking a different value.
+    Index,
+}
+
+// Which watchpoint fired, from the instruction at pc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchHit {
+    // The first watched address that was written.
+    Memory {
+        pc: u16,
+        address: u16,
+        old: u8,
+        new: u8,
+    },
+    Register {
+        pc: u16,
+        register: usize,
+        old: u8,
+        new: u8,
+    },
+    Index {
+        pc: u16,
+        old: u16,
+        new: u16,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    watchpoints: Vec<Watchpoint>,
+    // Watched memory as it was before the current instruction, reused between steps
+    watched_memory: Vec<u8>,
+    cycles_per_frame: usize,
+    // Instructions run since the last timer tick
+    cycles: usize,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            watched_memory: Vec::new(),
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            cycles: 0,
+        }
+    }
+
+    // How many instructions make a 60Hz frame, 12 by default like a 700Hz CPU, the same as Chip8::run_frame.
+    pub fn with_cycles_per_frame(mut self, cycles: usize) -> Debugger {
+        self.cycles_per_frame = cycles.max(1);
+        self
+    }
+
+    // Returns false if there already was a breakpoint at address.
+    pub fn add_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.insert(address)
+    }
+
+    // Returns false if there was no breakpoint at address.
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    // Breakpoint addresses in ascending order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    // Returns false if the same watchpoint was already set, or if it's for a register past VF.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+        if self.watchpoints.contains(&watchpoint)
+            || matches!(watchpoint, Watchpoint::Register(register) if register > 0xF)
+        {
+            return false;
+        }
+        self.watchpoints.push(watchpoint);
+        true
+    }
+
+    // Returns false if there was no such watchpoint.
+    pub fn remove_watchpoint(&mut self, watchpoint: Watchpoint) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|&other| other != watchpoint);
+        self.watchpoints.len() != count
+    }
+
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    // Watchpoints in the order they were added.
+    pub fn watchpoints(&self) -> impl Iterator<Item = Watchpoint> + '_ {
+        self.watchpoints.iter().copied()
+    }
+
+    // Runs exactly one instruction, breakpoints don't apply but watchpoints do.
+    pub fn step(&mut self, chip8: &mut Chip8) -> Result<StopReason, Chip8Error> {
+        if chip8.is_halted() {
+            return Ok(StopReason::Halted);
+        }
+        // Display wait: skip ahead to the next frame instead of stalling
+        if chip8.is_waiting_for_vblank() {
+            self.end_frame(chip8);
+        }
+
+        let pc = chip8.program_counter();
+        let registers = *chip8.registers();
+        let index = chip8.index();
+        self.save_watched_memory(chip8);
+
+        chip8.step()?;
+        self.cycles += 1;
+        if self.cycles >= self.cycles_per_frame {
+            self.end_frame(chip8);
+        }
+
+        if let Some(hit) = self.check_watchpoints(chip8, pc, &registers, index) {
+            return Ok(StopReason::Watchpoint(hit));
+        }
+        Ok(if chip8.is_halted() {
+            StopReason::Halted
+        } else if chip8.waiting_for_key().is_some() {
+            StopReason::WaitingForKey
+        } else {
+            StopReason::Done
+        })
+    }
+
+    // Like step, but a call runs all the way until it returns.
+    pub fn step_over(&mut self, chip8: &mut Chip8, limit: usize) -> Result<StopReason, Chip8Error> {
+        let is_call = chip8.opcode_at(chip8.program_counter()) & 0xF000 == 0x2000;
+        let depth = chip8.stack().len();
+
+        let reason = self.step(chip8)?;
+        if !is_call || reason != StopReason::Done || chip8.stack().len() <= depth {
+            return Ok(reason);
+        }
+        // The call itself already ran, so a breakpoint on the subroutine's first line counts
+        match self.run_until(chip8, limit, false, |chip8| chip8.stack().len() <= depth)? {
+            StopReason::Returned => Ok(StopReason::Done),
+            reason => Ok(reason),
+        }
+    }
+
+    // Runs until the current subroutine returns to its caller.
+    // Outside of any subroutine this is the same as run.
+    pub fn finish(&mut self, chip8: &mut Chip8, limit: usize) -> Result<StopReason, Chip8Error> {
+        let depth = chip8.stack().len();
+        self.run_until(chip8, limit, true, |chip8| chip8.stack().len() < depth)
+    }
+
+    // Runs until a breakpoint, or until limit instructions ran.
+    pub fn run(&mut self, chip8: &mut Chip8, limit: usize) -> Result<StopReason, Chip8Error> {
+        self.run_until(chip8, limit, true, |_| false)
+    }
+
+    // Usually the breakpoint at the starting address is skipped,
+    // otherwise continuing from one would never move.
+    fn run_until(
+        &mut self,
+        chip8: &mut Chip8,
+        limit: usize,
+        skip_first_breakpoint: bool,
+        returned: impl Fn(&Chip8) -> bool,
+    ) -> Result<StopReason, Chip8Error> {
+        for executed in 0..limit {
+            let pc = chip8.program_counter();
+            if (executed > 0 || !skip_first_breakpoint) && self.breakpoints.contains(&pc) {
+                return Ok(StopReason::Breakpoint(pc));
+            }
+
+            let reason = self.step(chip8)?;
+            if reason != StopReason::Done {
+                return Ok(reason);
+            }
+            if returned(chip8) {
+                return Ok(StopReason::Returned);
+            }
+        }
+        Ok(StopReason::Limit)
+    }
+
+    fn save_watched_memory(&mut self, chip8: &Chip8) {
+        self.watched_memory.clear();
+        for &watchpoint in &self.watchpoints {
+            if let Watchpoint::Memory { start, end } = watchpoint {
+                self.watched_memory.extend_from_slice(
+                    watched_range(chip8, start, end).map_or(&[], |range| &chip8.memory()[range]),
+                );
+            }
+        }
+    }
+
+    // Watchpoints are checked in the order they were added, the first one that fired wins.
+    fn check_watchpoints(
+        &self,
+        chip8: &Chip8,
+        pc: u16,
+        registers: &[u8; 16],
+        index: u16,
+    ) -> Option<WatchHit> {
+        let mut saved = 0;
+        for &watchpoint in &self.watchpoints {
+            match watchpoint {
+                Watchpoint::Memory { start, end } => {
+                    let Some(range) = watched_range(chip8, start, end) else {
+                        continue;
+                    };
+                    let old = &self.watched_memory[saved..saved + range.len()];
+                    saved += range.len();
+
+                    let Some(written) = chip8.last_write.clone() else {
+                        continue;
+                    };
+                    if let Some(address) = range.clone().find(|address| written.contains(address)) {
+                        return Some(WatchHit::Memory {
+                            pc,
+                            address: address as u16,
+                            old: old[address - range.start],
+                            new: chip8.memory()[address],
+                        });
+                    }
+                }
+                Watchpoint::Register(register) => {
+                    let (old, new) = (registers[register], chip8.registers()[register]);
+                    if old != new {
+                        return Some(WatchHit::Register {
+                            pc,
+                            register,
+                            old,
+                            new,
+                        });
+                    }
+                }
+                Watchpoint::Index => {
+                    if index != chip8.index() {
+                        return Some(WatchHit::Index {
+                            pc,
+                            old: index,
+                            new: chip8.index(),
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn end_frame(&mut self, chip8: &mut Chip8) {
+        chip8.tick_timers();
+        self.cycles = 0;
+    }
+
+    // Registers, timers and stack as a few lines of text.
+    pub fn dump_state(chip8: &Chip8) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "PC {:03X}  opcode {:04X}  I {:03X}  SP {}  DT {:02X}  ST {:02X}",
+            chip8.program_counter(),
+            chip8.opcode_at(chip8.program_counter()),
+            chip8.index(),
+            chip8.stack().len(),
+            chip8.delay_timer(),
+            chip8.sound_timer()
+        );
+        for (half, registers) in chip8.registers().chunks(8).enumerate() {
+            for (offset, value) in registers.iter().enumerate() {
+                let _ = write!(out, "V{:X} {:02X}  ", half * 8 + offset, value);
+            }
+            out.truncate(out.trim_end().len());
+            out.push('\n');
+        }
+        out.push_str("Stack:");
+        if chip8.stack().is_empty() {
+            out.push_str(" empty");
+        }
+        for address in chip8.stack() {
+            let _ = write!(out, " {:03X}", address);
+        }
+        out.push('\n');
+        out
+    }
+
+    // Hex dump of len bytes starting at start, 16 per line, clamped to the end of memory.
+    pub fn dump_memory(chip8: &Chip8, start: usize, len: usize) -> String {
+        let memory = chip8.memory();
+        let start = start.min(memory.len());
+        let end = start.saturating_add(len).min(memory.len());
+
+        let mut out = String::new();
+        for (line, bytes) in memory[start..end].chunks(16).enumerate() {
+            let _ = write!(out, "{:04X}:", start + line * 16);
+            for byte in bytes {
+                let _ = write!(out, " {:02X}", byte);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The part of a memory watchpoint inside the address space, None if there's nothing left.
+fn watched_range(chip8: &Chip8, start: u16, end: u16) -> Option<Range<usize>> {
+    let end = (end as usize + 1).min(chip8.memory().len());
+    let start = start as usize;
+    (start < end).then_some(start..end)
+}
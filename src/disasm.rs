@@ -0,0 +1,281 @@
+// Turns ROM bytes back into readable instructions.
+// Code is found by following jumps, calls and skips from the load address, like the CPU would,
+// so sprites and other data stuck between routines are listed as bytes instead of bogus opcodes.
+
+use crate::{Variant, instruction::Instruction};
+
+// How mnemonics are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    // Octo assembly: `v0 := 0x05`, `if v1 != v2 then`, `sprite v0 v1 5`.
+    #[default]
+    Octo,
+    // The classic mnemonics from Cowgod's reference: `LD V0, #05`, `SE V1, V2`, `DRW V0, V1, 5`.
+    Classic,
+}
+
+impl Syntax {
+    pub const NAMES: [&'static str; 2] = ["octo", "classic"];
+
+    pub fn from_name(name: &str) -> Option<Syntax> {
+        match name.to_ascii_lowercase().as_str() {
+            "octo" => Some(Syntax::Octo),
+            "classic" | "cowgod" => Some(Syntax::Classic),
+            _ => None,
+        }
+    }
+}
+
+// One line of the listing: an instruction, or a few bytes of data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub address: u16,
+    // The opcode (4 bytes for XO-CHIP's i := long), or up to 8 bytes of data.
+    pub bytes: Vec<u8>,
+    // None for data
+    instruction: Option<Instruction>,
+}
+
+impl Line {
+    pub fn is_code(&self) -> bool {
+        self.instruction.is_some()
+    }
+
+    // The first two bytes as a big endian opcode, None for data.
+    pub fn opcode(&self) -> Option<u16> {
+        self.instruction
+            .map(|_| u16::from_be_bytes([self.bytes[0], self.bytes[1]]))
+    }
+
+    pub fn mnemonic(&self, syntax: Syntax) -> String {
+        match self.instruction {
+            Some(instruction) => format_instruction(instruction, &self.bytes, syntax),
+            None => format_data(&self.bytes, syntax),
+        }
+    }
+}
+
+// Disassembles a ROM loaded at base_addr, accepting every SUPER-CHIP and XO-CHIP instruction.
+pub fn disassemble(bytes: &[u8], base_addr: u16) -> Vec<Line> {
+    disassemble_variant(bytes, base_addr, Variant::XoChip)
+}
+
+// Same as disassemble, with only the instructions of variant counting as code.
+pub fn disassemble_variant(bytes: &[u8], base_addr: u16, variant: Variant) -> Vec<Line> {
+    let base = base_addr as usize;
+    // Instruction found at each offset, and which bytes belong to one
+    let mut decoded: Vec<Option<Instruction>> = vec![None; bytes.len()];
+    let mut code = vec![false; bytes.len()];
+
+    let opcode_at = |offset: usize| {
+        (offset + 1 < bytes.len()).then(|| u16::from_be_bytes([bytes[offset], bytes[offset + 1]]))
+    };
+    let length_at = |offset: usize| {
+        if variant == Variant::XoChip && opcode_at(offset) == Some(0xF000) {
+            4
+        } else {
+            2
+        }
+    };
+
+    let mut pending = vec![base];
+    while let Some(address) = pending.pop() {
+        let mut offset = match address.checked_sub(base) {
+            Some(offset) => offset,
+            None => continue,
+        };
+
+        // Stop at the end of the ROM, at code already traced and at anything that isn't code
+        while let Some(opcode) = opcode_at(offset) {
+            let Some(instruction) = Instruction::decode(opcode, variant) else {
+                break;
+            };
+            let length = length_at(offset);
+            if offset + length > bytes.len() || code[offset..offset + length].contains(&true) {
+                break;
+            }
+            decoded[offset] = Some(instruction);
+            code[offset..offset + length].fill(true);
+
+            let next = offset + length;
+            match instruction {
+                Instruction::Jump(target) => {
+                    pending.push(target as usize);
+                    break;
+                }
+                Instruction::Call(target) => pending.push(target as usize),
+                // Computed jumps and the end of a routine, there's nothing to follow
+                Instruction::JumpOffset(_) | Instruction::Return | Instruction::Exit => break,
+                Instruction::SEQ(..)
+                | Instruction::SNEQ(..)
+                | Instruction::SEQR(..)
+                | Instruction::SNEQR(..)
+                | Instruction::SkipIfKey(_)
+                | Instruction::SkipIfNotKey(_) => pending.push(base + next + length_at(next)),
+                _ => {}
+            }
+            offset = next;
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        if let Some(instruction) = decoded[offset] {
+            let length = length_at(offset);
+            lines.push(Line {
+                address: (base + offset) as u16,
+                bytes: bytes[offset..offset + length].to_vec(),
+                instruction: Some(instruction),
+            });
+            offset += length;
+        } else {
+            let end = (offset..bytes.len())
+                .find(|&end| code[end] || end - offset == 8)
+                .unwrap_or(bytes.len());
+            lines.push(Line {
+                address: (base + offset) as u16,
+                bytes: bytes[offset..end].to_vec(),
+                instruction: None,
+            });
+            offset = end;
+        }
+    }
+    lines
+}
+
+fn format_data(bytes: &[u8], syntax: Syntax) -> String {
+    let bytes: Vec<String> = match syntax {
+        Syntax::Octo => bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect(),
+        Syntax::Classic => bytes.iter().map(|byte| format!("#{:02X}", byte)).collect(),
+    };
+    match syntax {
+        Syntax::Octo => bytes.join(" "),
+        Syntax::Classic => format!("DB {}", bytes.join(", ")),
+    }
+}
+
+pub(crate) fn format_instruction(instruction: Instruction, bytes: &[u8], syntax: Syntax) -> String {
+    match syntax {
+        Syntax::Octo => octo(instruction, bytes),
+        Syntax::Classic => classic(instruction, bytes),
+    }
+}
+
+// The operand of i := long is the word after the opcode.
+fn long_operand(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[2], bytes[3]])
+}
+
+fn octo(instruction: Instruction, bytes: &[u8]) -> String {
+    use Instruction::*;
+    match instruction {
+        Clear => "clear".to_string(),
+        Return => "return".to_string(),
+        Jump(nnn) => format!("jump 0x{:03X}", nnn),
+        JumpOffset(nnn) => format!("jump0 0x{:03X}", nnn),
+        Call(nnn) => format!(":call 0x{:03X}", nnn),
+        // Octo writes skips as the condition under which the next instruction runs
+        SEQ(x, nn) => format!("if v{:x} != 0x{:02X} then", x, nn),
+        SNEQ(x, nn) => format!("if v{:x} == 0x{:02X} then", x, nn),
+        SEQR(x, y) => format!("if v{:x} != v{:x} then", x, y),
+        SNEQR(x, y) => format!("if v{:x} == v{:x} then", x, y),
+        Set(x, nn) => format!("v{:x} := 0x{:02X}", x, nn),
+        Add(x, nn) => format!("v{:x} += 0x{:02X}", x, nn),
+        SetRegister(x, y) => format!("v{:x} := v{:x}", x, y),
+        OR(x, y) => format!("v{:x} |= v{:x}", x, y),
+        AND(x, y) => format!("v{:x} &= v{:x}", x, y),
+        XOR(x, y) => format!("v{:x} ^= v{:x}", x, y),
+        AddRegister(x, y) => format!("v{:x} += v{:x}", x, y),
+        Subtract(x, y) => format!("v{:x} -= v{:x}", x, y),
+        SubtractInv(x, y) => format!("v{:x} =- v{:x}", x, y),
+        RShift(x, y) => format!("v{:x} >>= v{:x}", x, y),
+        LShift(x, y) => format!("v{:x} <<= v{:x}", x, y),
+        SetIndex(nnn) => format!("i := 0x{:03X}", nnn),
+        Random(x, nn) => format!("v{:x} := random 0x{:02X}", x, nn),
+        Display(x, y, n) => format!("sprite v{:x} v{:x} {}", x, y, n),
+        SkipIfKey(x) => format!("if v{:x} -key then", x),
+        SkipIfNotKey(x) => format!("if v{:x} key then", x),
+        GetDelayTimer(x) => format!("v{:x} := delay", x),
+        GetKey(x) => format!("v{:x} := key", x),
+        SetDelayTimer(x) => format!("delay := v{:x}", x),
+        SetSoundTimer(x) => format!("buzzer := v{:x}", x),
+        AddToIndex(x) => format!("i += v{:x}", x),
+        GetFontCharacter(x) => format!("i := hex v{:x}", x),
+        BinaryToDecimal(x) => format!("bcd v{:x}", x),
+        StoreMemory(x) => format!("save v{:x}", x),
+        LoadMemory(x) => format!("load v{:x}", x),
+        ScrollDown(n) => format!("scroll-down {}", n),
+        ScrollRight => "scroll-right".to_string(),
+        ScrollLeft => "scroll-left".to_string(),
+        Exit => "exit".to_string(),
+        LowRes => "lores".to_string(),
+        HighRes => "hires".to_string(),
+        GetBigFontCharacter(x) => format!("i := bighex v{:x}", x),
+        StoreFlags(x) => format!("saveflags v{:x}", x),
+        LoadFlags(x) => format!("loadflags v{:x}", x),
+        ScrollUp(n) => format!("scroll-up {}", n),
+        LongIndex => format!("i := long 0x{:04X}", long_operand(bytes)),
+        StoreRange(x, y) => format!("save v{:x} - v{:x}", x, y),
+        LoadRange(x, y) => format!("load v{:x} - v{:x}", x, y),
+        SelectPlanes(n) => format!("plane {}", n),
+        LoadAudioPattern => "audio".to_string(),
+        SetPitch(x) => format!("pitch := v{:x}", x),
+    }
+}
+
+fn classic(instruction: Instruction, bytes: &[u8]) -> String {
+    use Instruction::*;
+    match instruction {
+        Clear => "CLS".to_string(),
+        Return => "RET".to_string(),
+        Jump(nnn) => format!("JP #{:03X}", nnn),
+        JumpOffset(nnn) => format!("JP V0, #{:03X}", nnn),
+        Call(nnn) => format!("CALL #{:03X}", nnn),
+        SEQ(x, nn) => format!("SE V{:X}, #{:02X}", x, nn),
+        SNEQ(x, nn) => format!("SNE V{:X}, #{:02X}", x, nn),
+        SEQR(x, y) => format!("SE V{:X}, V{:X}", x, y),
+        SNEQR(x, y) => format!("SNE V{:X}, V{:X}", x, y),
+        Set(x, nn) => format!("LD V{:X}, #{:02X}", x, nn),
+        Add(x, nn) => format!("ADD V{:X}, #{:02X}", x, nn),
+        SetRegister(x, y) => format!("LD V{:X}, V{:X}", x, y),
+        OR(x, y) => format!("OR V{:X}, V{:X}", x, y),
+        AND(x, y) => format!("AND V{:X}, V{:X}", x, y),
+        XOR(x, y) => format!("XOR V{:X}, V{:X}", x, y),
+        AddRegister(x, y) => format!("ADD V{:X}, V{:X}", x, y),
+        Subtract(x, y) => format!("SUB V{:X}, V{:X}", x, y),
+        SubtractInv(x, y) => format!("SUBN V{:X}, V{:X}", x, y),
+        RShift(x, y) => format!("SHR V{:X}, V{:X}", x, y),
+        LShift(x, y) => format!("SHL V{:X}, V{:X}", x, y),
+        SetIndex(nnn) => format!("LD I, #{:03X}", nnn),
+        Random(x, nn) => format!("RND V{:X}, #{:02X}", x, nn),
+        Display(x, y, n) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        SkipIfKey(x) => format!("SKP V{:X}", x),
+        SkipIfNotKey(x) => format!("SKNP V{:X}", x),
+        GetDelayTimer(x) => format!("LD V{:X}, DT", x),
+        GetKey(x) => format!("LD V{:X}, K", x),
+        SetDelayTimer(x) => format!("LD DT, V{:X}", x),
+        SetSoundTimer(x) => format!("LD ST, V{:X}", x),
+        AddToIndex(x) => format!("ADD I, V{:X}", x),
+        GetFontCharacter(x) => format!("LD F, V{:X}", x),
+        BinaryToDecimal(x) => format!("LD B, V{:X}", x),
+        StoreMemory(x) => format!("LD [I], V{:X}", x),
+        LoadMemory(x) => format!("LD V{:X}, [I]", x),
+        ScrollDown(n) => format!("SCD {}", n),
+        ScrollRight => "SCR".to_string(),
+        ScrollLeft => "SCL".to_string(),
+        Exit => "EXIT".to_string(),
+        LowRes => "LOW".to_string(),
+        HighRes => "HIGH".to_string(),
+        GetBigFontCharacter(x) => format!("LD HF, V{:X}", x),
+        StoreFlags(x) => format!("LD R, V{:X}", x),
+        LoadFlags(x) => format!("LD V{:X}, R", x),
+        ScrollUp(n) => format!("SCU {}", n),
+        LongIndex => format!("LD I, LONG #{:04X}", long_operand(bytes)),
+        StoreRange(x, y) => format!("SAVE V{:X}-V{:X}", x, y),
+        LoadRange(x, y) => format!("LOAD V{:X}-V{:X}", x, y),
+        SelectPlanes(n) => format!("PLANE {}", n),
+        LoadAudioPattern => "AUDIO".to_string(),
+        SetPitch(x) => format!("PITCH V{:X}", x),
+    }
+}
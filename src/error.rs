@@ -0,0 +1,57 @@
+use std::{error::Error, fmt, io};
+
+// Everything that can go wrong while loading or running a ROM.
+#[derive(Debug)]
+pub enum Chip8Error {
+    // The opcode fetched at pc doesn't decode to any instruction.
+    UnknownOpcode { pc: u16, opcode: u16 },
+    // A Call at pc found the stack already full.
+    StackOverflow { pc: u16 },
+    // A Return at pc found the stack empty.
+    StackUnderflow { pc: u16 },
+    // The instruction at pc tried to read or write past the end of memory.
+    MemoryOutOfBounds { pc: u16, address: usize },
+    // The ROM doesn't fit between the load address and the end of memory.
+    RomTooLarge { size: usize, max: usize },
+    // A save state is corrupted, truncated or from an incompatible version.
+    InvalidSaveState(&'static str),
+    // Reading the ROM from disk failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown opcode 0x{:04X} at 0x{:03X}", opcode, pc)
+            }
+            Chip8Error::StackOverflow { pc } => write!(f, "stack overflow at 0x{:03X}", pc),
+            Chip8Error::StackUnderflow { pc } => write!(f, "stack underflow at 0x{:03X}", pc),
+            Chip8Error::MemoryOutOfBounds { pc, address } => write!(
+                f,
+                "memory access out of bounds at 0x{:03X} (address 0x{:X})",
+                pc, address
+            ),
+            Chip8Error::RomTooLarge { size, max } => {
+                write!(f, "ROM is {} bytes, at most {} fit in memory", size, max)
+            }
+            Chip8Error::InvalidSaveState(reason) => write!(f, "invalid save state: {}", reason),
+            Chip8Error::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Chip8Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Chip8Error {
+    fn from(e: io::Error) -> Self {
+        Chip8Error::Io(e)
+    }
+}
@@ -0,0 +1,162 @@
+// Running the machine a 60 Hz frame at a time, and keeping frames in step with the wall clock.
+//
+//     let mut clock = FrameClock::new();
+//     loop {
+//         // read input, then
+//         if let Some(frame) = clock.run(&mut chip8)? && frame.display_changed {
+//             // draw chip8.framebuffer()
+//         }
+//         thread::sleep(clock.until_next());
+//     }
+
+use std::time::{Duration, Instant};
+
+use crate::{Chip8, Chip8Error, Timing};
+
+// 700 instructions a second, the usual CHIP-8 speed.
+pub const DEFAULT_CYCLES_PER_FRAME: usize = 12;
+// How many late frames FrameClock runs back to back before giving up on the backlog.
+const DEFAULT_MAX_CATCH_UP: u32 = 5;
+
+// What happened during run_frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameResult {
+    // Something was drawn, cleared or scrolled
+    pub display_changed: bool,
+    // The sound timer is running
+    pub sound_on: bool,
+    // FX0A is waiting for a key press and release
+    pub waiting_for_key: bool,
+    // SUPER-CHIP exit ran, no frame will do anything anymore
+    pub halted: bool,
+}
+
+impl FrameResult {
+    // Two frames in a row, as one: the display changed if it changed in either, the rest is how the second one ended.
+    pub fn then(self, next: FrameResult) -> FrameResult {
+        FrameResult {
+            display_changed: self.display_changed || next.display_changed,
+            ..next
+        }
+    }
+}
+
+impl Chip8 {
+    // Instructions run_frame executes with fixed timing. VIP timing fills frames with cycles instead.
+    pub fn with_cycles_per_frame(mut self, cycles: usize) -> Chip8 {
+        self.cycles_per_frame = cycles.max(1);
+        self
+    }
+
+    pub fn cycles_per_frame(&self) -> usize {
+        self.cycles_per_frame
+    }
+
+    // Runs one 60 Hz frame: the frame's instructions, then a timer tick.
+    // Execution stops early if the program waits for a key or for vblank.
+    pub fn run_frame(&mut self) -> Result<FrameResult, Chip8Error> {
+        // Whatever was dirty before stays dirty for take_dirty_region, but doesn't count for this frame
+        let dirty = self.dirty.take();
+        let cycles = match self.timing {
+            Timing::Fixed => self.cycles_per_frame,
+            Timing::CosmacVip => usize::MAX,
+        };
+        let result = self.run(cycles);
+        let display_changed = self.dirty.is_some();
+        self.dirty = match (dirty, self.dirty) {
+            (Some(before), Some(now)) => Some(before.union(now)),
+            (before, now) => before.or(now),
+        };
+        result?;
+
+        self.tick_timers();
+        Ok(FrameResult {
+            display_changed,
+            sound_on: self.beeper(),
+            waiting_for_key: self.waiting_for_key.is_some(),
+            halted: self.halted,
+        })
+    }
+}
+
+// Decides when frames are due on a fixed 60 Hz timeline, so sleeping too long or too short never adds up to drift.
+// When it falls behind it runs the late frames back to back, up to a limit, then drops the rest of the backlog.
+pub struct FrameClock {
+    period: Duration,
+    // When the next frame is due
+    next: Instant,
+    max_catch_up: u32,
+}
+
+impl Default for FrameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameClock {
+    // 60 frames a second, the first one due right away.
+    pub fn new() -> FrameClock {
+        FrameClock {
+            period: Duration::from_nanos(1_000_000_000 / 60),
+            next: Instant::now(),
+            max_catch_up: DEFAULT_MAX_CATCH_UP,
+        }
+    }
+
+    pub fn with_rate(mut self, hz: u32) -> FrameClock {
+        self.period = Duration::from_nanos(1_000_000_000 / u64::from(hz.max(1)));
+        self
+    }
+
+    // Late frames run at most this many at once, a longer backlog (a paused window, a suspended laptop) is dropped.
+    pub fn with_max_catch_up(mut self, frames: u32) -> FrameClock {
+        self.max_catch_up = frames.max(1);
+        self
+    }
+
+    // Starts the timeline over from now, after a pause for instance.
+    pub fn reset(&mut self) {
+        self.reset_at(Instant::now());
+    }
+
+    pub fn reset_at(&mut self, now: Instant) {
+        self.next = now;
+    }
+
+    // How many frames should run now.
+    pub fn due_frames(&mut self) -> u32 {
+        self.due_frames_at(Instant::now())
+    }
+
+    pub fn due_frames_at(&mut self, now: Instant) -> u32 {
+        if now < self.next {
+            return 0;
+        }
+        let behind = ((now - self.next).as_nanos() / self.period.as_nanos()) as u32 + 1;
+        if behind > self.max_catch_up {
+            self.next = now + self.period;
+            return self.max_catch_up;
+        }
+        self.next += self.period * behind;
+        behind
+    }
+
+    // Time left before the next frame is due, zero if it's late.
+    pub fn until_next(&self) -> Duration {
+        self.next.saturating_duration_since(Instant::now())
+    }
+
+    // Runs the frames that are due, None if none are. A CPU error stops right away.
+    pub fn run(&mut self, chip8: &mut Chip8) -> Result<Option<FrameResult>, Chip8Error> {
+        let mut result: Option<FrameResult> = None;
+        for _ in 0..self.due_frames() {
+            let frame = chip8.run_frame()?;
+            result = Some(result.map_or(frame, |before| before.then(frame)));
+            if frame.halted {
+                break;
+            }
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{HIRES_DISPLAY_SIZE_X, HIRES_DISPLAY_SIZE_Y};
+
+// Read-only view of the screen at the current resolution.
+// This is what renderers and embedders get instead of the display buffer itself.
+#[derive(Clone, Copy)]
+pub struct Framebuffer<'a> {
+    width: usize,
+    height: usize,
+    pixels: &'a [[u8; HIRES_DISPLAY_SIZE_X]; HIRES_DISPLAY_SIZE_Y],
+}
+
+impl<'a> Framebuffer<'a> {
+    pub(crate) fn new(
+        width: usize,
+        height: usize,
+        pixels: &'a [[u8; HIRES_DISPLAY_SIZE_X]; HIRES_DISPLAY_SIZE_Y],
+    ) -> Framebuffer<'a> {
+        Framebuffer {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // Color of the pixel at x, y: 0 is off, otherwise the planes it's lit on (always 1 outside XO-CHIP).
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y][x]
+    }
+
+    // Row y of the visible area.
+    pub fn row(&self, y: usize) -> &'a [u8] {
+        &self.pixels[y][..self.width]
+    }
+
+    // Visible rows, top to bottom, each one width pixels long.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let width = self.width;
+        self.pixels[..self.height]
+            .iter()
+            .map(move |row| &row[..width])
+    }
+}
+
+// Part of the screen that changed, as rows and as the rectangle around every changed pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRegion {
+    // One bit per row, the display is at most 64 rows tall.
+    rows: u64,
+    left: usize,
+    top: usize,
+    // Exclusive
+    right: usize,
+    bottom: usize,
+}
+
+impl DirtyRegion {
+    pub(crate) fn pixel(x: usize, y: usize) -> DirtyRegion {
+        DirtyRegion {
+            rows: 1 << y,
+            left: x,
+            top: y,
+            right: x + 1,
+            bottom: y + 1,
+        }
+    }
+
+    pub(crate) fn full(width: usize, height: usize) -> DirtyRegion {
+        DirtyRegion {
+            rows: u64::MAX >> (64 - height),
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        }
+    }
+
+    pub(crate) fn union(self, other: DirtyRegion) -> DirtyRegion {
+        DirtyRegion {
+            rows: self.rows | other.rows,
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    pub fn is_row_dirty(&self, y: usize) -> bool {
+        y < 64 && self.rows & (1 << y) != 0
+    }
+
+    // Indices of the changed rows, top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = usize> + use<> {
+        let rows = self.rows;
+        (0..64).filter(move |&y| rows & (1 << y) != 0)
+    }
+
+    // Smallest rectangle holding every change, as (x, y, width, height).
+    pub fn bounds(&self) -> (usize, usize, usize, usize) {
+        (
+            self.left,
+            self.top,
+            self.right - self.left,
+            self.bottom - self.top,
+        )
+    }
+}
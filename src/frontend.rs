@@ -0,0 +1,94 @@
+use rust_8::{Chip8, Chip8Error, FrameClock, FrameResult, Rewind, audio::AudioSink};
+use std::io::{Write, stdout};
+
+use crate::Config;
+
+pub mod debug;
+pub mod headless;
+pub mod terminal;
+pub mod window;
+
+// Which frontend drives the emulator, picked with --frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frontend {
+    Terminal,
+    Window,
+}
+
+impl Frontend {
+    pub fn from_name(name: &str) -> Option<Frontend> {
+        match name.to_ascii_lowercase().as_str() {
+            "terminal" => Some(Frontend::Terminal),
+            "window" => Some(Frontend::Window),
+            _ => None,
+        }
+    }
+}
+
+// Both frontends stop on CPU errors, this is what they tell the user.
+pub fn report_cpu_error(e: &Chip8Error) {
+    match e {
+        Chip8Error::UnknownOpcode { .. } => eprintln!(
+            "CPU Error: {} (the ROM may target another CHIP-8 variant)",
+            e
+        ),
+        _ => eprintln!("CPU Error: {}", e),
+    }
+}
+
+// The history --rewind and --rewind-every ask for, None when rewinding is off.
+pub fn rewind_history(config: &Config) -> Option<Rewind> {
+    if config.rewind_seconds == 0 {
+        return None;
+    }
+    let snapshots = config.rewind_seconds as usize * 60 / config.rewind_interval as usize;
+    Some(
+        Rewind::new()
+            .with_interval(config.rewind_interval)
+            .with_capacity(snapshots),
+    )
+}
+
+// FrameClock::run, with rewinding: every due frame is recorded into the history,
+// or while the rewind key is held, steps back one snapshot instead of running.
+pub fn run_frames(
+    clock: &mut FrameClock,
+    chip8: &mut Chip8,
+    rewind: Option<&mut Rewind>,
+    rewinding: bool,
+) -> Result<Option<FrameResult>, Chip8Error> {
+    let Some(rewind) = rewind else {
+        return clock.run(chip8);
+    };
+    let mut result: Option<FrameResult> = None;
+    for _ in 0..clock.due_frames() {
+        let frame = if rewinding {
+            rewind.step_back(chip8)?;
+            FrameResult {
+                display_changed: true,
+                ..FrameResult::default()
+            }
+        } else {
+            let frame = chip8.run_frame()?;
+            rewind.record(chip8);
+            frame
+        };
+        result = Some(result.map_or(frame, |before| before.then(frame)));
+        if frame.halted {
+            break;
+        }
+    }
+    Ok(result)
+}
+
+// Rings the terminal bell every time the beeper starts, the closest thing to sound without an audio device.
+pub struct Bell;
+
+impl AudioSink for Bell {
+    fn beeper(&mut self, on: bool) {
+        if on {
+            let _ = stdout().write_all(b"\x07");
+            let _ = stdout().flush();
+        }
+    }
+}
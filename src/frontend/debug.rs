@@ -0,0 +1,290 @@
+use rust_8::{Chip8, Debugger, StopReason, Timing, WatchHit, Watchpoint};
+use std::error::Error;
+use std::io::{BufRead, Write, stdin, stdout};
+
+use super::report_cpu_error;
+use crate::Config;
+
+// Continue and finish give up after this many instructions,
+// so a ROM spinning forever doesn't hang the prompt.
+const DEFAULT_LIMIT: usize = 1_000_000;
+
+// Line based debugger on stdin/stdout. No screen is drawn, use the screen command to look at it.
+pub fn run(mut chip8: Chip8, config: &Config) -> Result<(), Box<dyn Error>> {
+    // With VIP timing frames end on their own when their cycles run out
+    let cycles_per_frame = match chip8.timing() {
+        Timing::Fixed => chip8.cycles_per_frame(),
+        Timing::CosmacVip => usize::MAX,
+    };
+    let mut debugger = Debugger::new().with_cycles_per_frame(cycles_per_frame);
+    let mut last_command = String::from("step");
+
+    println!(
+        "Debugging {}. Type help for the list of commands.",
+        config.rom_path
+    );
+    print!("{}", Debugger::dump_state(&chip8));
+
+    let mut lines = stdin().lock().lines();
+    loop {
+        print!("(rust-8) ");
+        stdout().flush()?;
+        let Some(line) = lines.next() else { break };
+        let line = line?;
+
+        // An empty line repeats the last command, handy for stepping
+        let line = if line.trim().is_empty() {
+            last_command.clone()
+        } else {
+            line.trim().to_string()
+        };
+        last_command = line.clone();
+
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+
+        let result = match command {
+            "s" | "step" => {
+                let count = match args.first() {
+                    Some(count) => match count.parse::<usize>() {
+                        Ok(count) => count,
+                        Err(_) => {
+                            println!("Invalid step count '{}'", count);
+                            continue;
+                        }
+                    },
+                    None => 1,
+                };
+                let mut result = Ok(StopReason::Done);
+                for _ in 0..count {
+                    result = debugger.step(&mut chip8);
+                    if !matches!(result, Ok(StopReason::Done)) {
+                        break;
+                    }
+                }
+                result
+            }
+            "n" | "next" => debugger.step_over(&mut chip8, DEFAULT_LIMIT),
+            "f" | "finish" => debugger.finish(&mut chip8, DEFAULT_LIMIT),
+            "c" | "continue" => debugger.run(&mut chip8, DEFAULT_LIMIT),
+            "b" | "break" => {
+                match args.first().and_then(|address| parse_hex(address)) {
+                    Some(address) if debugger.add_breakpoint(address) => {
+                        println!("Breakpoint at {:03X}", address)
+                    }
+                    Some(address) => println!("There already is a breakpoint at {:03X}", address),
+                    None => println!("Usage: break <ADDR>"),
+                }
+                continue;
+            }
+            "d" | "delete" => {
+                match args.first().and_then(|address| parse_hex(address)) {
+                    Some(address) if debugger.remove_breakpoint(address) => {
+                        println!("Deleted breakpoint at {:03X}", address)
+                    }
+                    Some(address) => println!("No breakpoint at {:03X}", address),
+                    None => {
+                        debugger.clear_breakpoints();
+                        println!("Deleted all breakpoints");
+                    }
+                }
+                continue;
+            }
+            "bl" | "breakpoints" => {
+                let addresses: Vec<String> = debugger
+                    .breakpoints()
+                    .map(|address| format!("{:03X}", address))
+                    .collect();
+                if addresses.is_empty() {
+                    println!("No breakpoints");
+                } else {
+                    println!("Breakpoints: {}", addresses.join(" "));
+                }
+                continue;
+            }
+            "w" | "watch" => {
+                match args.first().and_then(|text| parse_watchpoint(text)) {
+                    Some(watchpoint) if debugger.add_watchpoint(watchpoint) => {
+                        println!("Watching {}", describe(watchpoint))
+                    }
+                    Some(watchpoint) => println!("Already watching {}", describe(watchpoint)),
+                    None => println!("Usage: watch <ADDR>[-<END>] | v<X> | i"),
+                }
+                continue;
+            }
+            "unwatch" => {
+                match args.first().map(|text| parse_watchpoint(text)) {
+                    Some(Some(watchpoint)) if debugger.remove_watchpoint(watchpoint) => {
+                        println!("Stopped watching {}", describe(watchpoint))
+                    }
+                    Some(Some(watchpoint)) => println!("Not watching {}", describe(watchpoint)),
+                    Some(None) => println!("Usage: unwatch [<ADDR>[-<END>] | v<X> | i]"),
+                    None => {
+                        debugger.clear_watchpoints();
+                        println!("Deleted all watchpoints");
+                    }
+                }
+                continue;
+            }
+            "wl" | "watchpoints" => {
+                let watched: Vec<String> = debugger.watchpoints().map(describe).collect();
+                if watched.is_empty() {
+                    println!("No watchpoints");
+                } else {
+                    println!("Watching: {}", watched.join(", "));
+                }
+                continue;
+            }
+            "r" | "regs" => {
+                print!("{}", Debugger::dump_state(&chip8));
+                continue;
+            }
+            "m" | "mem" => {
+                let start = args.first().and_then(|address| parse_hex(address));
+                let len = args.get(1).and_then(|len| len.parse().ok()).unwrap_or(64);
+                let start = start.unwrap_or(chip8.index());
+                print!(
+                    "{}",
+                    Debugger::dump_memory(&chip8, start as usize, len as usize)
+                );
+                continue;
+            }
+            "screen" => {
+                print!("{}", chip8.framebuffer().to_ascii());
+                continue;
+            }
+            "key" => {
+                match args
+                    .first()
+                    .and_then(|key| u8::from_str_radix(key, 16).ok())
+                {
+                    Some(key) if key < 16 => {
+                        if chip8.keyboard[key as usize] {
+                            chip8.key_up(key);
+                            println!("Key {:X} released", key);
+                        } else {
+                            chip8.key_down(key);
+                            println!("Key {:X} pressed", key);
+                        }
+                    }
+                    _ => println!("Usage: key <0-F>, toggles the key"),
+                }
+                continue;
+            }
+            "h" | "help" => {
+                print_commands();
+                continue;
+            }
+            "q" | "quit" => break,
+            _ => {
+                println!("Unknown command '{}', type help for the list", command);
+                continue;
+            }
+        };
+
+        match result {
+            Ok(reason) => {
+                match reason {
+                    StopReason::Breakpoint(address) => println!("Breakpoint at {:03X}", address),
+                    StopReason::Returned => println!("Returned"),
+                    StopReason::WaitingForKey => {
+                        println!("Waiting for a key, press and release one with the key command")
+                    }
+                    StopReason::Halted => println!("The program exited"),
+                    StopReason::Limit => println!("Stopped after {} instructions", DEFAULT_LIMIT),
+                    StopReason::Watchpoint(hit) => report_watch_hit(hit),
+                    StopReason::Done => {}
+                }
+                print!("{}", Debugger::dump_state(&chip8));
+            }
+            Err(e) => report_cpu_error(&e),
+        }
+    }
+
+    Ok(())
+}
+
+// Addresses are always hex here, with or without 0x.
+fn parse_hex(text: &str) -> Option<u16> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).ok()
+}
+
+// ADDR or ADDR-END for memory, v0-vf for registers, i for the index register.
+fn parse_watchpoint(text: &str) -> Option<Watchpoint> {
+    let lower = text.to_ascii_lowercase();
+    if lower == "i" {
+        return Some(Watchpoint::Index);
+    }
+    if let Some(register) = lower.strip_prefix('v') {
+        return usize::from_str_radix(register, 16)
+            .ok()
+            .filter(|&register| register < 16)
+            .map(Watchpoint::Register);
+    }
+    let (start, end) = match lower.split_once('-') {
+        Some((start, end)) => (parse_hex(start)?, parse_hex(end)?),
+        None => (parse_hex(&lower)?, parse_hex(&lower)?),
+    };
+    (start <= end).then_some(Watchpoint::Memory { start, end })
+}
+
+fn describe(watchpoint: Watchpoint) -> String {
+    match watchpoint {
+        Watchpoint::Memory { start, end } if start == end => format!("{:03X}", start),
+        Watchpoint::Memory { start, end } => format!("{:03X}-{:03X}", start, end),
+        Watchpoint::Register(register) => format!("V{:X}", register),
+        Watchpoint::Index => "I".to_string(),
+    }
+}
+
+fn report_watch_hit(hit: WatchHit) {
+    match hit {
+        WatchHit::Memory {
+            pc,
+            address,
+            old,
+            new,
+        } => println!(
+            "Watchpoint: {:03X} wrote {:03X}: {:02X} -> {:02X}",
+            pc, address, old, new
+        ),
+        WatchHit::Register {
+            pc,
+            register,
+            old,
+            new,
+        } => println!(
+            "Watchpoint: {:03X} changed V{:X}: {:02X} -> {:02X}",
+            pc, register, old, new
+        ),
+        WatchHit::Index { pc, old, new } => println!(
+            "Watchpoint: {:03X} changed I: {:03X} -> {:03X}",
+            pc, old, new
+        ),
+    }
+}
+
+fn print_commands() {
+    println!("  step, s [N]              Run N instructions (default 1)");
+    println!("  next, n                  Run one instruction, calls run until they return");
+    println!("  finish, f                Run until the current subroutine returns");
+    println!("  continue, c              Run until a breakpoint");
+    println!("  break, b <ADDR>          Set a breakpoint");
+    println!("  delete, d [ADDR]         Delete a breakpoint, or all of them");
+    println!("  breakpoints, bl          List breakpoints");
+    println!("  watch, w <ADDR>[-<END>]  Stop when the ROM writes to memory there");
+    println!("  watch, w v<X> | i        Stop when a V register or I changes");
+    println!("  unwatch [WATCH]          Delete a watchpoint, or all of them");
+    println!("  watchpoints, wl          List watchpoints");
+    println!("  regs, r                  Show registers, timers and stack");
+    println!("  mem, m [ADDR] [LEN]      Dump memory (default: 64 bytes at I)");
+    println!("  screen                   Print the screen");
+    println!("  key <0-F>                Press or release a key");
+    println!("  quit, q                  Exit");
+    println!("An empty line repeats the last command.");
+}
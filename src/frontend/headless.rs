@@ -0,0 +1,90 @@
+use rust_8::{Chip8, PALETTE, render::encode_png};
+use std::error::Error;
+use std::fs;
+
+use super::report_cpu_error;
+use crate::Config;
+
+// A key the script presses at the start of a frame and releases hold frames later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptedKey {
+    pub frame: u64,
+    pub key: u8,
+    pub hold: u64,
+}
+
+// FRAME:KEY or FRAME:KEY:HOLD entries, separated by commas or whitespace. KEY is a hex digit.
+// Anything after a # is a comment, so scripts can live in a file.
+pub fn parse_key_script(script: &str) -> Result<Vec<ScriptedKey>, String> {
+    let mut keys = Vec::new();
+    for line in script.lines() {
+        let code = line.split('#').next().unwrap_or_default();
+        for entry in code
+            .split([',', ' ', '\t'])
+            .filter(|entry| !entry.is_empty())
+        {
+            let parts: Vec<&str> = entry.split(':').collect();
+            let invalid = || format!("Invalid key entry '{}', expected FRAME:KEY[:HOLD]", entry);
+            let (frame, key, hold) = match parts[..] {
+                [frame, key] => (frame, key, "1"),
+                [frame, key, hold] => (frame, key, hold),
+                _ => return Err(invalid()),
+            };
+            let key = u8::from_str_radix(key, 16).ok().filter(|&key| key < 16);
+            let hold = hold.parse().ok().filter(|&hold| hold > 0);
+            match (frame.parse(), key, hold) {
+                (Ok(frame), Some(key), Some(hold)) => keys.push(ScriptedKey { frame, key, hold }),
+                _ => return Err(invalid()),
+            }
+        }
+    }
+    Ok(keys)
+}
+
+// Runs a fixed number of frames as fast as possible with nothing on screen, then writes the screen out.
+// Exits with status 1 if the CPU stops on an error, so scripts can tell.
+pub fn run(mut chip8: Chip8, config: &Config) -> Result<(), Box<dyn Error>> {
+    let frames = config.frames.unwrap_or_default();
+
+    let mut failed = false;
+    for frame in 0..frames {
+        for scripted in &config.keys {
+            if scripted.frame == frame {
+                chip8.key_down(scripted.key);
+            } else if scripted.frame + scripted.hold == frame {
+                chip8.key_up(scripted.key);
+            }
+        }
+        if let Err(e) = chip8.run_frame() {
+            report_cpu_error(&e);
+            eprintln!("Stopped at frame {} of {}", frame, frames);
+            failed = true;
+            break;
+        }
+    }
+
+    match config.screen.as_deref() {
+        Some("-") => print!("{}", chip8.framebuffer().to_ascii()),
+        Some(path) if path.to_ascii_lowercase().ends_with(".png") => {
+            let mut palette = PALETTE;
+            if let Some(background) = config.background {
+                palette[0] = background;
+            }
+            if let Some(foreground) = config.foreground {
+                palette[1] = foreground;
+            }
+            let png = encode_png(chip8.framebuffer(), &palette, config.scale as usize);
+            fs::write(path, png).map_err(|e| format!("Can't write {}: {}", path, e))?;
+        }
+        Some(path) => fs::write(path, chip8.framebuffer().to_ascii())
+            .map_err(|e| format!("Can't write {}: {}", path, e))?,
+        None => {}
+    }
+
+    if failed {
+        // Lets the audio sink and the tracer finish their files first
+        drop(chip8);
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use crossterm::{
+    cursor::MoveTo,
+    event::{
+        Event, KeyCode, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags, poll, read,
+    },
+    execute,
+    style::Print,
+    terminal::{
+        Clear, ClearType, disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement,
+    },
+};
+use rust_8::{
+    Chip8, FrameClock,
+    render::{Renderer, TerminalRenderer},
+};
+use std::error::Error;
+use std::fs;
+use std::io::stdout;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{report_cpu_error, rewind_history, run_frames};
+use crate::Config;
+
+// Without release events a key counts as held this long after its last press or repeat.
+// Long enough to bridge the gap between terminal key repeats, short enough to feel like a tap.
+const FALLBACK_HOLD: Duration = Duration::from_millis(150);
+
+pub fn run(mut chip8: Chip8, config: &Config) -> Result<(), Box<dyn Error>> {
+    let mut renderer = TerminalRenderer::new();
+
+    enable_raw_mode()?;
+
+    // Terminals speaking the kitty keyboard protocol report releases when asked to.
+    // The Windows console always does.
+    let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(
+            stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
+    let reports_releases = enhanced || cfg!(windows);
+    // When each key was last seen going down, for terminals that only report presses
+    let mut last_pressed: [Option<Instant>; 16] = [None; 16];
+
+    let mut clock = FrameClock::new();
+    let mut rewind = rewind_history(config);
+    // When Backspace was last seen going down, None once it's let go
+    let mut rewind_pressed: Option<Instant> = None;
+    let mut rewinding = false;
+
+    println!("Starting emulator... Press ESC to exit.");
+
+    'main: loop {
+        // 1. Handle input events
+        while poll(Duration::from_millis(1))? {
+            if let Event::Key(key_event) = read()? {
+                if let Some(key) = keypad_index(key_event.code) {
+                    match key_event.kind {
+                        KeyEventKind::Release => chip8.key_up(key),
+                        _ => {
+                            chip8.key_down(key);
+                            last_pressed[key as usize] = Some(Instant::now());
+                        }
+                    }
+                    continue;
+                }
+                if key_event.code == KeyCode::Backspace && rewind.is_some() {
+                    match key_event.kind {
+                        KeyEventKind::Release => rewind_pressed = None,
+                        _ => {
+                            if rewind_pressed.is_none() {
+                                show_status(&chip8, "Rewinding")?;
+                            }
+                            rewind_pressed = Some(Instant::now());
+                        }
+                    }
+                    continue;
+                }
+                // Everything else acts on the press
+                if key_event.kind == KeyEventKind::Release {
+                    continue;
+                }
+                match key_event.code {
+                    KeyCode::Esc => break 'main,
+                    KeyCode::F(slot @ 1..=4) => {
+                        let message = save_slot(&chip8, &config.rom_path, slot);
+                        show_status(&chip8, &message)?;
+                    }
+                    KeyCode::F(key @ 5..=8) => {
+                        let message = load_slot(&mut chip8, &config.rom_path, key - 4);
+                        show_status(&chip8, &message)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // 2. Without release events, keys let go on their own once the repeats stop
+        if !reports_releases {
+            for (key, pressed) in last_pressed.iter_mut().enumerate() {
+                if pressed.is_some_and(|time| time.elapsed() >= FALLBACK_HOLD) {
+                    *pressed = None;
+                    chip8.key_up(key as u8);
+                }
+            }
+            if rewind_pressed.is_some_and(|time| time.elapsed() >= FALLBACK_HOLD) {
+                rewind_pressed = None;
+            }
+        }
+        if rewinding && rewind_pressed.is_none() {
+            // The keys held in the last loaded snapshot aren't held anymore
+            for key in 0..16 {
+                chip8.key_up(key);
+            }
+            show_status(&chip8, "")?;
+        }
+        rewinding = rewind_pressed.is_some();
+
+        match run_frames(&mut clock, &mut chip8, rewind.as_mut(), rewinding) {
+            // Whatever changed, including a loaded save state
+            Ok(Some(_)) => {
+                if let Some(region) = chip8.take_dirty_region() {
+                    renderer.render_region(chip8.framebuffer(), region)?;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                report_cpu_error(&e);
+                break;
+            }
+        }
+
+        // SUPER-CHIP programs can quit on their own
+        if chip8.is_halted() {
+            break;
+        }
+
+        // Keys are polled every millisecond in between frames
+        thread::sleep(clock.until_next().min(Duration::from_millis(1)));
+    }
+
+    if enhanced {
+        execute!(stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    println!("Emulator stopped.");
+    Ok(())
+}
+
+fn keypad_index(code: KeyCode) -> Option<u8> {
+    let KeyCode::Char(c) = code else {
+        return None;
+    };
+    match c.to_ascii_lowercase() {
+        '1' => Some(0x1),
+        '2' => Some(0x2),
+        '3' => Some(0x3),
+        '4' => Some(0xC),
+        'q' => Some(0x4),
+        'w' => Some(0x5),
+        'e' => Some(0x6),
+        'r' => Some(0xD),
+        'a' => Some(0x7),
+        's' => Some(0x8),
+        'd' => Some(0x9),
+        'f' => Some(0xE),
+        'z' => Some(0xA),
+        'x' => Some(0x0),
+        'c' => Some(0xB),
+        'v' => Some(0xF),
+        _ => None,
+    }
+}
+
+// Save states live next to the ROM, one file per slot.
+fn slot_path(rom_path: &str, slot: u8) -> String {
+    format!("{}.{}.state", rom_path, slot)
+}
+
+fn save_slot(chip8: &Chip8, rom_path: &str, slot: u8) -> String {
+    match fs::write(slot_path(rom_path, slot), chip8.save_state()) {
+        Ok(()) => format!("Saved to slot {}", slot),
+        Err(e) => format!("Saving slot {} failed: {}", slot, e),
+    }
+}
+
+fn load_slot(chip8: &mut Chip8, rom_path: &str, slot: u8) -> String {
+    let state = match fs::read(slot_path(rom_path, slot)) {
+        Ok(state) => state,
+        Err(e) => return format!("Loading slot {} failed: {}", slot, e),
+    };
+    match chip8.load_state(&state) {
+        Ok(()) => format!("Loaded slot {}", slot),
+        Err(e) => format!("Loading slot {} failed: {}", slot, e),
+    }
+}
+
+// One line of text right below the screen.
+fn show_status(chip8: &Chip8, message: &str) -> Result<(), Box<dyn Error>> {
+    let row = chip8.display_height() as u16 + 1;
+    execute!(
+        stdout(),
+        MoveTo(0, row),
+        Clear(ClearType::CurrentLine),
+        Print(message)
+    )?;
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use rust_8::{
+    Chip8, FrameClock, PALETTE, Rewind,
+    render::{Renderer, WindowRenderer},
+};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+use winit::{
+    application::ApplicationHandler,
+    dpi::LogicalSize,
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{Window, WindowId},
+};
+
+use super::{report_cpu_error, rewind_history, run_frames};
+use crate::Config;
+
+pub fn run(chip8: Chip8, config: &Config) -> Result<(), Box<dyn Error>> {
+    let event_loop = EventLoop::new()?;
+    let mut palette = PALETTE;
+    if let Some(background) = config.background {
+        palette[0] = background;
+    }
+    if let Some(foreground) = config.foreground {
+        palette[1] = foreground;
+    }
+    let rom_name = Path::new(&config.rom_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut app = WindowApp {
+        chip8,
+        title: format!("rust-8 - {}", rom_name),
+        scale: config.scale,
+        palette,
+        window: None,
+        renderer: None,
+        paused: false,
+        clock: FrameClock::new(),
+        rewind: rewind_history(config),
+        rewinding: false,
+        error: None,
+    };
+
+    event_loop.run_app(&mut app)?;
+
+    match app.error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+// Same layout as the terminal frontend, by physical position so it works on any keyboard layout.
+fn keypad_index(code: KeyCode) -> Option<u8> {
+    match code {
+        KeyCode::Digit1 => Some(0x1),
+        KeyCode::Digit2 => Some(0x2),
+        KeyCode::Digit3 => Some(0x3),
+        KeyCode::Digit4 => Some(0xC),
+        KeyCode::KeyQ => Some(0x4),
+        KeyCode::KeyW => Some(0x5),
+        KeyCode::KeyE => Some(0x6),
+        KeyCode::KeyR => Some(0xD),
+        KeyCode::KeyA => Some(0x7),
+        KeyCode::KeyS => Some(0x8),
+        KeyCode::KeyD => Some(0x9),
+        KeyCode::KeyF => Some(0xE),
+        KeyCode::KeyZ => Some(0xA),
+        KeyCode::KeyX => Some(0x0),
+        KeyCode::KeyC => Some(0xB),
+        KeyCode::KeyV => Some(0xF),
+        _ => None,
+    }
+}
+
+struct WindowApp {
+    chip8: Chip8,
+    title: String,
+    // Window pixels per CHIP-8 low resolution pixel, to begin with
+    scale: u32,
+    palette: [(u8, u8, u8); 4],
+    // Both are created once the event loop is running.
+    window: Option<Arc<Window>>,
+    renderer: Option<WindowRenderer>,
+    // Nothing runs and the timers stand still while paused
+    paused: bool,
+    clock: FrameClock,
+    // Frames step back through the history instead of running while Backspace is held
+    rewind: Option<Rewind>,
+    rewinding: bool,
+    // The event loop can't return errors by itself, so they wait here.
+    error: Option<Box<dyn Error>>,
+}
+
+impl WindowApp {
+    fn fail(&mut self, event_loop: &ActiveEventLoop, e: Box<dyn Error>) {
+        self.error = Some(e);
+        event_loop.exit();
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        // Otherwise the time spent paused would be caught up all at once
+        self.clock.reset();
+
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.set_paused(self.paused);
+        }
+        if let Some(window) = &self.window {
+            if self.paused {
+                window.set_title(&format!("{} (paused, P to resume)", self.title));
+            } else {
+                window.set_title(&self.title);
+            }
+            window.request_redraw();
+        }
+    }
+
+    fn set_rewinding(&mut self, rewinding: bool) {
+        if rewinding == self.rewinding {
+            return;
+        }
+        self.rewinding = rewinding;
+        if !rewinding {
+            // The keys held in the last loaded snapshot aren't held anymore
+            self.release_keys();
+        }
+        if let Some(window) = &self.window {
+            if rewinding {
+                window.set_title(&format!("{} (rewinding)", self.title));
+            } else {
+                window.set_title(&self.title);
+            }
+        }
+    }
+
+    fn release_keys(&mut self) {
+        for key in 0..16 {
+            self.chip8.key_up(key);
+        }
+    }
+}
+
+impl ApplicationHandler for WindowApp {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+
+        // High resolution needs at least 128x64 to show every pixel
+        let attributes = Window::default_attributes()
+            .with_title(&self.title)
+            .with_inner_size(LogicalSize::new(64 * self.scale, 32 * self.scale))
+            .with_min_inner_size(LogicalSize::new(128, 64));
+        let window = match event_loop.create_window(attributes) {
+            Ok(window) => Arc::new(window),
+            Err(e) => return self.fail(event_loop, e.into()),
+        };
+        match WindowRenderer::new(window.clone()) {
+            Ok(renderer) => self.renderer = Some(renderer.with_palette(self.palette)),
+            Err(e) => return self.fail(event_loop, e.into()),
+        }
+        self.window = Some(window);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+
+            WindowEvent::Resized(size) => {
+                if let Some(renderer) = self.renderer.as_mut()
+                    && let Err(e) = renderer.resize(size.width, size.height)
+                {
+                    self.fail(event_loop, e);
+                }
+            }
+
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state,
+                        repeat,
+                        ..
+                    },
+                ..
+            } => {
+                if code == KeyCode::Escape {
+                    event_loop.exit();
+                } else if code == KeyCode::KeyP {
+                    if state == ElementState::Pressed && !repeat {
+                        self.toggle_pause();
+                    }
+                } else if code == KeyCode::Backspace && self.rewind.is_some() {
+                    self.set_rewinding(state == ElementState::Pressed);
+                } else if let Some(key) = keypad_index(code) {
+                    match state {
+                        ElementState::Pressed => self.chip8.key_down(key),
+                        ElementState::Released => self.chip8.key_up(key),
+                    }
+                }
+            }
+
+            // Key releases while another window has focus never arrive
+            WindowEvent::Focused(false) => {
+                self.set_rewinding(false);
+                self.release_keys();
+            }
+
+            WindowEvent::RedrawRequested => {
+                if let Some(renderer) = self.renderer.as_mut()
+                    && let Err(e) = renderer.render(self.chip8.framebuffer())
+                {
+                    self.fail(event_loop, e);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    // Runs the machine between window events, with the same pacing as the terminal frontend.
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if self.paused {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        }
+
+        match run_frames(
+            &mut self.clock,
+            &mut self.chip8,
+            self.rewind.as_mut(),
+            self.rewinding,
+        ) {
+            Ok(Some(frame)) => {
+                if frame.display_changed
+                    && let Some(window) = &self.window
+                {
+                    window.request_redraw();
+                }
+                if frame.halted {
+                    event_loop.exit();
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                report_cpu_error(&e);
+                event_loop.exit();
+                return;
+            }
+        }
+
+        event_loop.set_control_flow(ControlFlow::WaitUntil(
+            Instant::now() + self.clock.until_next(),
+        ));
+    }
+}
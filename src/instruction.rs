@@ -0,0 +1,171 @@
+use crate::Variant;
+
+// Contiene tutte l'instruction set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) enum Instruction {
+    Clear,
+    Jump(u16),
+    JumpOffset(u16),
+    Call(u16),
+    Return,
+    SEQ(usize, u8),
+    SNEQ(usize, u8),
+    SEQR(usize, usize),
+    SNEQR(usize, usize),
+    Set(usize, u8),
+    SetRegister(usize, usize),
+    OR(usize, usize),
+    AND(usize, usize),
+    XOR(usize, usize),
+    Add(usize, u8),
+    AddRegister(usize, usize),
+    Subtract(usize, usize),
+    SubtractInv(usize, usize),
+    Random(usize, u8),
+    LShift(usize, usize),
+    RShift(usize, usize),
+    SkipIfKey(usize),
+    SkipIfNotKey(usize),
+    GetDelayTimer(usize),
+    SetDelayTimer(usize),
+    SetSoundTimer(usize),
+    AddToIndex(usize),
+    GetKey(usize),
+    GetFontCharacter(usize),
+    BinaryToDecimal(usize),
+    StoreMemory(usize),
+    LoadMemory(usize),
+    SetIndex(u16),
+    Display(usize, usize, u8),
+    // SUPER-CHIP
+    ScrollDown(u8),
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    LowRes,
+    HighRes,
+    GetBigFontCharacter(usize),
+    StoreFlags(usize),
+    LoadFlags(usize),
+    // XO-CHIP
+    ScrollUp(u8),
+    LongIndex,
+    StoreRange(usize, usize),
+    LoadRange(usize, usize),
+    SelectPlanes(u8),
+    LoadAudioPattern,
+    SetPitch(usize),
+}
+
+// Operand fields, as the bits of the opcode they take up.
+macro_rules! field {
+    (x) => {
+        0x0F00
+    };
+    (y) => {
+        0x00F0
+    };
+    (n) => {
+        0x000F
+    };
+    (nn) => {
+        0x00FF
+    };
+    (nnn) => {
+        0x0FFF
+    };
+}
+
+// Builds both decode and encode from one table, so the two can't disagree.
+// Every entry is the opcode with its operands zeroed, the instruction with the fields they come from,
+// and the first variant that has it.
+macro_rules! opcodes {
+    ($($opcode:literal => $name:ident $(($($field:ident),*))?, $since:ident;)*) => {
+        impl Instruction {
+            // None if the opcode isn't part of the variant's instruction set.
+            pub(crate) fn decode(opcode: u16, variant: Variant) -> Option<Instruction> {
+                $(
+                    let operands = 0 $($(| field!($field))*)?;
+                    if opcode & !operands == $opcode && variant.id() >= Variant::$since.id() {
+                        return Some(Instruction::$name $(($(unpack(opcode, field!($field)) as _),*))?);
+                    }
+                )*
+                None
+            }
+
+            // The opcode decode turns back into this instruction.
+            // For LongIndex that's only F000, the address follows in the next word.
+            pub(crate) fn encode(self) -> u16 {
+                match self {
+                    $(Instruction::$name $(($($field),*))? => {
+                        $opcode $($(| pack($field as u16, field!($field)))*)?
+                    })*
+                }
+            }
+        }
+    };
+}
+
+fn unpack(opcode: u16, field: u16) -> u16 {
+    (opcode & field) >> field.trailing_zeros()
+}
+
+fn pack(value: u16, field: u16) -> u16 {
+    (value << field.trailing_zeros()) & field
+}
+
+opcodes! {
+    0x00E0 => Clear, Chip8;                     // 00E0 - Clear screen
+    0x00EE => Return, Chip8;                    // 00EE - Return from subroutine
+    0x1000 => Jump(nnn), Chip8;                 // 1nnn - Jump to nnn
+    0x2000 => Call(nnn), Chip8;                 // 2nnn - Call subroutine at nnn
+    0x3000 => SEQ(x, nn), Chip8;                // 3xnn - Skip if v[x] is equal to nn
+    0x4000 => SNEQ(x, nn), Chip8;               // 4xnn - Skip if not equal
+    0x5000 => SEQR(x, y), Chip8;                // 5xy0 - Skip if v[x] and v[y] are equal
+    0x6000 => Set(x, nn), Chip8;                // 6xnn - Set Vx = nn
+    0x7000 => Add(x, nn), Chip8;                // 7xnn - Add nn to Vx
+    0x8000 => SetRegister(x, y), Chip8;
+    0x8001 => OR(x, y), Chip8;
+    0x8002 => AND(x, y), Chip8;
+    0x8003 => XOR(x, y), Chip8;
+    0x8004 => AddRegister(x, y), Chip8;
+    0x8005 => Subtract(x, y), Chip8;
+    0x8006 => RShift(x, y), Chip8;
+    0x8007 => SubtractInv(x, y), Chip8;
+    0x800E => LShift(x, y), Chip8;
+    0x9000 => SNEQR(x, y), Chip8;
+    0xA000 => SetIndex(nnn), Chip8;             // Annn - Set I = nnn
+    0xB000 => JumpOffset(nnn), Chip8;
+    0xC000 => Random(x, nn), Chip8;             // Cxnn - Random
+    0xD000 => Display(x, y, n), Chip8;          // Dxyn - Display sprite
+    0xE09E => SkipIfKey(x), Chip8;
+    0xE0A1 => SkipIfNotKey(x), Chip8;
+    0xF007 => GetDelayTimer(x), Chip8;
+    0xF00A => GetKey(x), Chip8;
+    0xF015 => SetDelayTimer(x), Chip8;
+    0xF018 => SetSoundTimer(x), Chip8;
+    0xF01E => AddToIndex(x), Chip8;
+    0xF029 => GetFontCharacter(x), Chip8;
+    0xF033 => BinaryToDecimal(x), Chip8;
+    0xF055 => StoreMemory(x), Chip8;
+    0xF065 => LoadMemory(x), Chip8;
+    // SUPER-CHIP
+    0x00C0 => ScrollDown(n), SuperChip;         // 00Cn - Scroll down n pixels
+    0x00FB => ScrollRight, SuperChip;           // 00FB - Scroll right 4 pixels
+    0x00FC => ScrollLeft, SuperChip;            // 00FC - Scroll left 4 pixels
+    0x00FD => Exit, SuperChip;                  // 00FD - Exit the interpreter
+    0x00FE => LowRes, SuperChip;                // 00FE - 64x32 mode
+    0x00FF => HighRes, SuperChip;               // 00FF - 128x64 mode
+    0xF030 => GetBigFontCharacter(x), SuperChip;
+    0xF075 => StoreFlags(x), SuperChip;
+    0xF085 => LoadFlags(x), SuperChip;
+    // XO-CHIP
+    0x00D0 => ScrollUp(n), XoChip;              // 00Dn - Scroll up n pixels
+    0xF000 => LongIndex, XoChip;                // F000 nnnn - Set I = nnnn
+    0x5002 => StoreRange(x, y), XoChip;
+    0x5003 => LoadRange(x, y), XoChip;
+    0xF001 => SelectPlanes(x), XoChip;
+    0xF002 => LoadAudioPattern, XoChip;
+    0xF03A => SetPitch(x), XoChip;
+}
@@ -1,10 +1,47 @@
-use std::{fs, path::Path};
-
-use rand::prelude::*;
+use std::{fs, ops::Range, path::Path};
+
+pub mod asm;
+pub mod audio;
+mod debugger;
+pub mod disasm;
+mod error;
+mod frame;
+mod framebuffer;
+mod instruction;
+pub mod octo;
+mod quirks;
+pub mod render;
+mod rewind;
+mod rng;
+pub mod snapshot;
+mod state;
+mod timing;
+mod trace;
+mod variant;
+
+pub use debugger::{Debugger, StopReason, WatchHit, Watchpoint};
+pub use error::Chip8Error;
+pub use frame::{DEFAULT_CYCLES_PER_FRAME, FrameClock, FrameResult};
+pub use framebuffer::{DirtyRegion, Framebuffer};
+pub use quirks::Quirks;
+pub use rewind::{DEFAULT_REWIND_CAPACITY, DEFAULT_REWIND_INTERVAL, Rewind};
+pub use timing::{Timing, VIP_CYCLES_PER_FRAME};
+pub use trace::Tracer;
+pub use variant::Variant;
+
+use audio::AudioSink;
+use instruction::Instruction;
+use rng::Rng;
 
 const MEMORY_SIZE_KB: usize = 4096;
+// XO-CHIP extends the address space to 64K.
+const XO_MEMORY_SIZE: usize = 0x10000;
 const DISPLAY_SIZE_X_KB: usize = 64;
 const DISPLAY_SIZE_Y_KB: usize = 32;
+// SUPER-CHIP high resolution mode. The display buffer is always this big,
+// in low resolution only the top left 64x32 corner is used.
+const HIRES_DISPLAY_SIZE_X: usize = 128;
+const HIRES_DISPLAY_SIZE_Y: usize = 64;
 const FONT_MEMORY_START: usize = 0x050;
 const FONT_MEMORY_END: usize = 0x09F;
 const FONT_SET: [u8; 80] = [
@@ -25,20 +62,50 @@ const FONT_SET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
+// SUPER-CHIP 8x10 font, right after the small one.
+const BIG_FONT_MEMORY_START: usize = 0x0A0;
+const BIG_FONT_SET: [u8; 160] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+    0x18, 0x3C, 0x66, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC, // B
+    0x3C, 0x7E, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0x7E, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
 const STACK_SIZE: usize = 16;
+// XO-CHIP display colors, indexed by the planes a pixel is lit on.
+pub const PALETTE: [(u8, u8, u8); 4] = [
+    (0x00, 0x00, 0x00), // Background
+    (0xFF, 0xFF, 0xFF), // Plane 1
+    (0xFF, 0x66, 0x00), // Plane 2
+    (0x99, 0x99, 0x99), // Both planes
+];
+const DEFAULT_ENTRY_POINT: u16 = 0x200;
+// XO-CHIP pitch that plays the audio pattern at 4000 Hz.
+const DEFAULT_PITCH: u8 = 64;
 
 // TODO: Handle input instructions
 //      - Publicly accessible keyboard DONE
 //      - Caller modifies the keyboard, instructions behavior regardless of what modifies it DONE
 //      - Input thread on the caller
-// TODO: Abstract display so that it can be used with terminal and window based solutions
-//      - Winit/wGPU
+// TODO: Abstract display so that it can be used with terminal and window based solutions DONE
+//      - Winit/wGPU DONE
 //      - Terminal DONE
 
 pub struct Chip8 {
     // Chip 8 Main Memory
-    // 4096 KB.
-    memory: [u8; MEMORY_SIZE_KB],
+    // 4096 KB, only XO-CHIP uses the rest of the 64K.
+    memory: [u8; XO_MEMORY_SIZE],
     // Program Counter
     program_counter: u16,
     // Index register
@@ -55,21 +122,85 @@ pub struct Chip8 {
     // 16 8-bit variable registers.
     v: [u8; 16],
 
-    // AS of now just a boolean array
-    // Chip-8 has black and white pixels.
-    display: [[bool; DISPLAY_SIZE_X_KB]; DISPLAY_SIZE_Y_KB],
+    // Memory written by the last instruction, for watchpoints.
+    last_write: Option<Range<usize>>,
+    // Logs every instruction when attached, not part of the machine state.
+    tracer: Option<Tracer>,
+
+    // Chip-8 has black and white pixels, XO-CHIP has two bit-planes.
+    // Every pixel holds one bit per plane, so it's a color from 0 to 3.
+    display: [[u8; HIRES_DISPLAY_SIZE_X]; HIRES_DISPLAY_SIZE_Y],
+    // Bit-planes affected by drawing, clearing and scrolling. Only XO-CHIP can change it.
+    planes: u8,
     update_display: bool,
+    // What changed since the last take_dirty_region call
+    dirty: Option<DirtyRegion>,
+    // SUPER-CHIP 128x64 mode
+    hires: bool,
+    // Set by the SUPER-CHIP exit instruction, nothing runs after it.
+    halted: bool,
+    // SUPER-CHIP RPL user flags, saved by FX75 and restored by FX85.
+    // They survive resets, like they survived reboots on the HP-48.
+    rpl: [u8; 16],
+
+    // XO-CHIP audio: a 128 bit sample pattern and its playback pitch.
+    audio_pattern: [u8; 16],
+    pitch: u8,
+    // Where the sound goes, not part of the machine state like the tracer.
+    audio: Option<Box<dyn AudioSink>>,
+    // Last beeper state the sink was told about
+    beeper_on: bool,
+    // Position in the audio pattern, in bits
+    pattern_phase: f64,
 
     pub keyboard: [bool; 16],
     waiting_for_key: Option<usize>,
+    // Key seen down while FX0A waits, the wait ends when it goes up
+    held_key: Option<u8>,
+    // Set by Display when the display_wait quirk is on, cleared by the next timer tick.
+    // With VIP timing also set when the frame runs out of cycles.
+    waiting_for_vblank: bool,
+    timing: Timing,
+    // Machine cycles spent in the current frame, with VIP timing
+    frame_cycles: u32,
+    // Instructions per run_frame with fixed timing
+    cycles_per_frame: usize,
+
+    // Drives CXNN. Part of the machine state, so save states restore it too.
+    rng: Rng,
+    // None draws a fresh seed from the OS
+    seed: Option<u64>,
+
+    quirks: Quirks,
+    variant: Variant,
+    // Where ROMs are loaded and execution starts.
+    entry_point: u16,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Chip8 {
     pub fn new() -> Chip8 {
         let mut chip8 = Chip8 {
-            memory: [0; MEMORY_SIZE_KB],
-            display: [[false; DISPLAY_SIZE_X_KB]; DISPLAY_SIZE_Y_KB],
+            memory: [0; XO_MEMORY_SIZE],
+            last_write: None,
+            tracer: None,
+            display: [[0; HIRES_DISPLAY_SIZE_X]; HIRES_DISPLAY_SIZE_Y],
+            planes: 1,
             update_display: true,
+            dirty: Some(DirtyRegion::full(DISPLAY_SIZE_X_KB, DISPLAY_SIZE_Y_KB)),
+            hires: false,
+            halted: false,
+            rpl: [0; 16],
+            audio_pattern: [0; 16],
+            pitch: DEFAULT_PITCH,
+            audio: None,
+            beeper_on: false,
+            pattern_phase: 0.0,
             program_counter: 0, // Potrebbe partire da qualcosa? Ha senso avere magari un builder?
             i: 0,
             stack: [0; STACK_SIZE],
@@ -79,128 +210,318 @@ impl Chip8 {
             v: [0; 16],
             keyboard: [false; 16],
             waiting_for_key: None,
+            held_key: None,
+            waiting_for_vblank: false,
+            timing: Timing::default(),
+            frame_cycles: 0,
+            cycles_per_frame: frame::DEFAULT_CYCLES_PER_FRAME,
+            rng: Rng::from_entropy(),
+            seed: None,
+            quirks: Quirks::default(),
+            variant: Variant::default(),
+            entry_point: DEFAULT_ENTRY_POINT,
         };
 
-        // Ogni istanza dell'emulatore deve avere i font caricati in memoria da 050 a 09F (80-159)
-        chip8.memory[FONT_MEMORY_START..FONT_MEMORY_END + 1].copy_from_slice(&FONT_SET);
+        chip8.load_fonts();
 
         chip8
     }
 
-    pub fn load_rom<P: AsRef<Path>>(mut self, rom_path: P) -> Result<Chip8, std::io::Error> {
+    // Selects the interpreter behaviors to emulate. Defaults to Quirks::LEGACY, what the emulator always did.
+    pub fn with_quirks(mut self, quirks: Quirks) -> Chip8 {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    // Selects the instruction set, CHIP-8 by default.
+    // Also switches to the variant's usual quirks, so call with_quirks afterwards to override them.
+    pub fn with_variant(mut self, variant: Variant) -> Chip8 {
+        self.variant = variant;
+        self.quirks = variant.default_quirks();
+        self
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    // Sets the address ROMs are loaded at, 0x200 by default.
+    // ETI-660 programs, for example, start at 0x600.
+    pub fn with_entry_point(mut self, entry_point: u16) -> Chip8 {
+        self.entry_point = entry_point;
+        self
+    }
+
+    pub fn entry_point(&self) -> u16 {
+        self.entry_point
+    }
+
+    // Makes CXNN reproducible: the same seed and inputs always give the same run.
+    // The generator goes back to the seed on every reset.
+    pub fn with_seed(mut self, seed: u64) -> Chip8 {
+        self.seed = Some(seed);
+        self.rng = Rng::from_seed(seed);
+        self
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn load_rom<P: AsRef<Path>>(self, rom_path: P) -> Result<Chip8, Chip8Error> {
         // Leggere il file contenente la rom, propaga eventuale errore al chiamante
-        // Più avanti sarò più specifico
         let rom = fs::read(rom_path)?;
 
+        self.load_rom_bytes(&rom)
+    }
+
+    pub fn load_rom_bytes(mut self, rom: &[u8]) -> Result<Chip8, Chip8Error> {
+        self.reload_rom(rom)?;
+        Ok(self)
+    }
+
+    // Resets the machine and loads another ROM, keeping quirks and entry point.
+    // On error the machine is left untouched.
+    pub fn reload_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        let start = self.entry_point as usize;
+        let max = self.memory_size().saturating_sub(start);
+        if rom.len() > max || start > self.memory_size() {
+            return Err(Chip8Error::RomTooLarge {
+                size: rom.len(),
+                max,
+            });
+        }
+
+        self.reset();
+
         // Carico la ROM in memoria
-        self.memory[0x200..0x200 + rom.len()].copy_from_slice(&rom);
+        self.memory[start..start + rom.len()].copy_from_slice(rom);
 
         // Inizializzo il PC
-        self.program_counter = 0x200;
+        self.program_counter = self.entry_point;
 
-        Ok(self)
+        Ok(())
+    }
+
+    // Brings the machine back to power-on state: memory holds only the font, everything else is zeroed.
+    pub fn reset(&mut self) {
+        self.memory.fill(0);
+        self.load_fonts();
+        self.display.fill([0; HIRES_DISPLAY_SIZE_X]);
+        self.planes = 1;
+        self.update_display = true;
+        self.hires = false;
+        self.mark_all_dirty();
+        self.halted = false;
+        self.program_counter = self.entry_point;
+        self.i = 0;
+        self.stack.fill(0);
+        self.sp = 0;
+        self.delay = 0;
+        self.sound = 0;
+        self.v.fill(0);
+        self.keyboard.fill(false);
+        self.waiting_for_key = None;
+        self.held_key = None;
+        self.waiting_for_vblank = false;
+        self.frame_cycles = 0;
+        self.audio_pattern.fill(0);
+        self.pitch = DEFAULT_PITCH;
+        self.pattern_phase = 0.0;
+        // A sink that heard the beeper go on hears it go off, the sound timer is zero now
+        if self.beeper_on
+            && let Some(sink) = self.audio.as_mut()
+        {
+            sink.beeper(false);
+        }
+        self.beeper_on = false;
+        self.last_write = None;
+        if let Some(seed) = self.seed {
+            self.rng = Rng::from_seed(seed);
+        }
+    }
+
+    // Ogni istanza dell'emulatore deve avere i font caricati in memoria da 050 a 09F (80-159)
+    // SUPER-CHIP big font follows at 0A0-13F.
+    fn load_fonts(&mut self) {
+        self.memory[FONT_MEMORY_START..FONT_MEMORY_END + 1].copy_from_slice(&FONT_SET);
+        self.memory[BIG_FONT_MEMORY_START..BIG_FONT_MEMORY_START + BIG_FONT_SET.len()]
+            .copy_from_slice(&BIG_FONT_SET);
     }
 
     // Fetches the next opcode from memory and increments the program counter.
+    // The program counter wraps around at the end of memory, like the address bus would.
     fn fetch(&mut self) -> u16 {
-        let opcode = (u16::from(self.memory[self.program_counter as usize]) << 8)
-            | u16::from(self.memory[self.program_counter.wrapping_add(1) as usize]);
-        self.program_counter = self.program_counter.wrapping_add(2);
+        let opcode = self.read_word(self.program_counter);
+        self.program_counter = self.wrap_address(self.program_counter.wrapping_add(2));
         opcode
     }
 
-    fn decode(&mut self, opcode: u16) -> Result<Instruction, String> {
-        let first_nibble = (opcode & 0xF000) >> 12;
-        let x = ((opcode & 0x0F00) >> 8) as usize; // Second nibble
-        let y = ((opcode & 0x00F0) >> 4) as usize; // Third nibble
-        let n = (opcode & 0x000F) as u8; // Fourth nibble
-        let nn = (opcode & 0x00FF) as u8; // Last byte
-        let nnn = opcode & 0x0FFF; // Last 12 bits
-
-        match first_nibble {
-            0x0 => {
-                match nn {
-                    0xE0 => Ok(Instruction::Clear),  // 00E0 - Clear screen
-                    0xEE => Ok(Instruction::Return), // 00EE - Return from subroutine
-                    _ => Err(format!("Unknown 0x0 instruction: 0x{:04X}", opcode)),
-                }
+    // 4K, or 64K on XO-CHIP.
+    fn memory_size(&self) -> usize {
+        self.variant.memory_size()
+    }
+
+    // Keeps an address inside the address space.
+    fn wrap_address(&self, address: u16) -> u16 {
+        (address as usize % self.memory_size()) as u16
+    }
+
+    // Reads the big endian word at address, wrapping around the end of memory.
+    fn read_word(&self, address: u16) -> u16 {
+        (u16::from(self.memory[self.wrap_address(address) as usize]) << 8)
+            | u16::from(self.memory[self.wrap_address(address.wrapping_add(1)) as usize])
+    }
+
+    // Address of the instruction being executed, which fetch already stepped over.
+    fn current_pc(&self) -> u16 {
+        self.wrap_address(self.program_counter.wrapping_sub(2))
+    }
+
+    // Skips the instruction following the current one.
+    // On XO-CHIP that can be the 4 bytes long F000 NNNN.
+    fn skip_next(&mut self) {
+        let long =
+            self.variant == Variant::XoChip && self.read_word(self.program_counter) == 0xF000;
+        let length = if long { 4 } else { 2 };
+        self.program_counter = self.wrap_address(self.program_counter.wrapping_add(length));
+    }
+
+    // Checks that len bytes starting at start are all inside memory.
+    // Instructions that go through the index register use this instead of indexing blindly.
+    // The error reports the first address of the range that is past the end.
+    fn memory_range(&self, start: usize, len: usize) -> Result<Range<usize>, Chip8Error> {
+        let size = self.memory_size();
+        if start + len > size {
+            let address = if start < size { size } else { start };
+            return Err(Chip8Error::MemoryOutOfBounds {
+                pc: self.current_pc(),
+                address,
+            });
+        }
+        Ok(start..start + len)
+    }
+
+    fn decode(&self, opcode: u16) -> Result<Instruction, Chip8Error> {
+        Instruction::decode(opcode, self.variant).ok_or_else(|| self.unknown_opcode(opcode))
+    }
+
+    // Moves the selected planes by dx, dy pixels. Pixels scrolled in are blank.
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = (
+            self.display_width() as isize,
+            self.display_height() as isize,
+        );
+        let (planes, previous) = (self.planes, self.display);
+
+        for y in 0..height {
+            for x in 0..width {
+                let (from_x, from_y) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&from_x) && (0..height).contains(&from_y) {
+                    previous[from_y as usize][from_x as usize]
+                } else {
+                    0
+                };
+
+                let pixel = &mut self.display[y as usize][x as usize];
+                *pixel = (*pixel & !planes) | (moved & planes);
             }
-            0x1 => Ok(Instruction::Jump(nnn)), // 1nnn - Jump to nnn
-            0x2 => Ok(Instruction::Call(nnn)), // 2nnn - Call subroutine at nnn
-            0x3 => Ok(Instruction::SEQ(x, nn)), // 3xnn - Skip if v[x] is equal to nn
-            0x4 => Ok(Instruction::SNEQ(x, nn)), // 4xnn - Skip if not equal
-            0x5 => Ok(Instruction::SEQR(x, y)), // 5xnn - Skip if v[x] and v[y] are not equal
-            0x6 => Ok(Instruction::Set(x, nn)), // 6xnn - Set Vx = nn
-            0x7 => Ok(Instruction::Add(x, nn)), // 7xnn - Add nn to Vx
-            0x8 => match n {
-                0 => Ok(Instruction::SetRegister(x, y)),
-                1 => Ok(Instruction::OR(x, y)),
-                2 => Ok(Instruction::AND(x, y)),
-                3 => Ok(Instruction::XOR(x, y)),
-                4 => Ok(Instruction::AddRegister(x, y)),
-                5 => Ok(Instruction::Subtract(x, y)),
-                6 => Ok(Instruction::RShift(x, y)),
-                7 => Ok(Instruction::SubtractInv(x, y)),
-                0xE => Ok(Instruction::LShift(x, y)),
-                _ => Err(format!("Unknown 0x8 instruction: 0x{:04X}", opcode)),
-            },
-
-            0x9 => Ok(Instruction::SNEQR(x, y)),
-            0xA => Ok(Instruction::SetIndex(nnn)), // Annn - Set I = nnn
-            0xB => Ok(Instruction::JumpOffset(nnn)),
-            0xC => Ok(Instruction::Random(x, nn)), // Cxnn - Random
-            0xD => Ok(Instruction::Display(x, y, n)), // Dxyn - Display sprite
-            0xE => match nn {
-                0x9E => Ok(Instruction::SkipIfKey(x)),
-                0xA1 => Ok(Instruction::SkipIfNotKey(x)),
-                _ => Err(format!("Unknown 0xE instruction: 0x{:04X}", opcode)),
-            },
-
-            0xF => match nn {
-                0x07 => Ok(Instruction::GetDelayTimer(x)),
-                0x15 => Ok(Instruction::SetDelayTimer(x)),
-                0x18 => Ok(Instruction::SetSoundTimer(x)),
-                0x0A => Ok(Instruction::GetKey(x)),
-                0x29 => Ok(Instruction::GetFontCharacter(x)),
-                0x33 => Ok(Instruction::BinaryToDecimal(x)),
-                0x1E => Ok(Instruction::AddToIndex(x)),
-                0x55 => Ok(Instruction::StoreMemory(x)),
-                0x65 => Ok(Instruction::LoadMemory(x)),
-                _ => Err(format!("Unknown 0xF instruction: 0x{:04X}", opcode)),
-            }, // Fx07 - Set v[x] to the current value of the display timer.
-            _ => Err(format!("Unimplemented instruction: 0x{:04X}", opcode)),
+        }
+
+        self.update_display = true;
+        self.mark_all_dirty();
+    }
+
+    // Records a changed pixel for take_dirty_region.
+    fn mark_dirty(&mut self, x: usize, y: usize) {
+        let pixel = DirtyRegion::pixel(x, y);
+        self.dirty = Some(self.dirty.map_or(pixel, |dirty| dirty.union(pixel)));
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty = Some(DirtyRegion::full(
+            self.display_width(),
+            self.display_height(),
+        ));
+    }
+
+    // Builds the error for an opcode that was just fetched, so it sits 2 bytes behind the PC.
+    fn unknown_opcode(&self, opcode: u16) -> Chip8Error {
+        Chip8Error::UnknownOpcode {
+            pc: self.current_pc(),
+            opcode,
         }
     }
 
     // Each n is a byte.
     // Remember that only 12 bytes out of 16 are actually used for value that are marked u16.
-    fn execute(&mut self, instruction: Instruction) -> Result<(), String> {
+    fn execute(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
         match instruction {
-            // Clears the screen.
+            // Clears the screen, only the selected planes on XO-CHIP.
             Instruction::Clear => {
-                self.display.fill([false; DISPLAY_SIZE_X_KB]);
+                let planes = self.planes;
+                for pixel in self.display.iter_mut().flatten() {
+                    *pixel &= !planes;
+                }
                 self.update_display = true;
+                self.mark_all_dirty();
+            }
+
+            // Scrolls the screen down by n pixels, the top rows become blank.
+            Instruction::ScrollDown(n) => self.scroll(0, n as isize),
+
+            // XO-CHIP only, scrolls the screen up by n pixels.
+            Instruction::ScrollUp(n) => self.scroll(0, -(n as isize)),
+
+            // Scrolls the screen right by 4 pixels.
+            Instruction::ScrollRight => self.scroll(4, 0),
+
+            // Scrolls the screen left by 4 pixels.
+            Instruction::ScrollLeft => self.scroll(-4, 0),
+
+            // Stops the machine, run won't execute anything else.
+            Instruction::Exit => self.halted = true,
+
+            // Switching resolution also clears the screen.
+            Instruction::LowRes | Instruction::HighRes => {
+                self.hires = matches!(instruction, Instruction::HighRes);
+                self.display.fill([0; HIRES_DISPLAY_SIZE_X]);
+                self.update_display = true;
+                self.mark_all_dirty();
             }
 
             // Jumps to memory address nnn
-            Instruction::Jump(nnn) => self.program_counter = nnn,
+            Instruction::Jump(nnn) => self.program_counter = self.wrap_address(nnn),
 
-            // TODO: Quirk
-            Instruction::JumpOffset(nnn) => self.program_counter = nnn + self.v[0] as u16,
+            // Jumps to nnn plus v[0], or to xnn plus v[x] on CHIP-48 and SUPER-CHIP.
+            Instruction::JumpOffset(nnn) => {
+                let offset = if self.quirks.jump_uses_vx {
+                    self.v[((nnn & 0x0F00) >> 8) as usize]
+                } else {
+                    self.v[0]
+                };
+                self.program_counter = self.wrap_address(nnn + offset as u16);
+            }
 
             // Adds to register v[x] the number nn.
             Instruction::Add(x, nn) => self.v[x] = self.v[x].wrapping_add(nn),
 
             Instruction::Subtract(x, y) => {
-                self.v[0xF] = if self.v[x] >= self.v[y] { 1 } else { 0 };
+                let flag = if self.v[x] >= self.v[y] { 1 } else { 0 };
 
                 self.v[x] = self.v[x].wrapping_sub(self.v[y]);
+                self.v[0xF] = flag;
             }
 
             Instruction::SubtractInv(x, y) => {
-                self.v[0xF] = if self.v[y] >= self.v[x] { 1 } else { 0 };
+                let flag = if self.v[y] >= self.v[x] { 1 } else { 0 };
 
                 self.v[x] = self.v[y].wrapping_sub(self.v[x]);
+                self.v[0xF] = flag;
             }
 
             // Set register v[x] content to nn.
@@ -213,89 +534,151 @@ impl Chip8 {
             Instruction::Display(x, y, n) => {
                 // Getting X and Y coordinates from the values in registers.
                 // Starting coordinates wrap around the display.
-                // Sprites that go over the borders must be clipped.
-                let (x, y) = (
-                    self.v[x] % DISPLAY_SIZE_X_KB as u8,
-                    self.v[y] % DISPLAY_SIZE_Y_KB as u8,
-                );
-
-                // Collision flag
-                self.v[0xF] = 0;
-
-                // For every sprite's row
-                for row in 0..n {
-                    // Load the sprite's n-th row
-                    let sprite_byte = self.memory[(self.i + row as u16) as usize];
-
-                    // For every bit in the row, check if needs to be turned on or off
-                    for col in 0..8 {
-                        // First, check if it should be drawn at all. Otherwise, just skip it.
-                        let (screen_x, screen_y) = ((x + col) as usize, (y + row) as usize);
-
-                        if screen_x >= DISPLAY_SIZE_X_KB || screen_y >= DISPLAY_SIZE_Y_KB {
-                            continue;
-                        }
+                // Sprites that go over the borders are clipped or wrapped, depending on the quirk.
+                let (width, height) = (self.display_width(), self.display_height());
+                let (x, y) = (self.v[x] as usize % width, self.v[y] as usize % height);
 
-                        // Questo u8 mi dice se il pixel di questa riga corrente dello sprite deve essere disegnato oppure no
-                        // Per esempio, il primo bit (da sx a dx) shiftato a destra di 7 va a finire nella prima posizione.
-                        // 10110000 >> 7 => 00000001 & 11111111 => 1. Il primo pixel della riga va acceso.
-                        // 10110000 >> 7 - 1 (processiamo il secondo bit significativo) => 00000000 & 11111111 => 0. Il secondo pixel va spento.
-                        let sprite_pixel = (sprite_byte >> (7 - col)) & 1;
+                // SUPER-CHIP draws DXY0 as a 16x16 sprite, two bytes per row.
+                let (rows, bytes_per_row) = if n == 0 && self.variant != Variant::Chip8 {
+                    (16, 2)
+                } else {
+                    (n as usize, 1)
+                };
+
+                // XO-CHIP draws one sprite per selected plane, stored one after the other.
+                let sprite_len = rows * bytes_per_row;
+                let plane_count = self.planes.count_ones() as usize;
+
+                // The whole sprite must be readable before anything is drawn.
+                let sprite = self.memory_range(self.i as usize, sprite_len * plane_count)?;
+
+                // Rows that hit a lit pixel, and rows that fell off the bottom of the screen.
+                let mut collided_rows = 0;
+                let mut clipped_rows = 0;
+
+                let planes = self.planes;
+                let selected = [1u8, 2].into_iter().filter(|plane| planes & plane != 0);
+                for (index, plane) in selected.enumerate() {
+                    let plane_start = sprite.start + index * sprite_len;
+
+                    // For every sprite's row
+                    for row in 0..rows {
+                        let mut screen_y = y + row;
+                        if screen_y >= height {
+                            if self.quirks.clip_sprites {
+                                clipped_rows += 1;
+                                continue;
+                            }
+                            screen_y %= height;
+                        }
 
-                        //
-                        if sprite_pixel == 1 {
-                            if self.display[screen_y][screen_x] == true {
-                                self.v[0xF] = 1;
+                        let mut collided = false;
+                        for byte in 0..bytes_per_row {
+                            // Load the sprite's n-th row
+                            let sprite_byte = self.memory[plane_start + row * bytes_per_row + byte];
+
+                            // For every bit in the row, check if needs to be turned on or off
+                            for col in 0..8 {
+                                // First, check if it should be drawn at all. Otherwise, just skip it.
+                                let mut screen_x = x + byte * 8 + col;
+                                if screen_x >= width {
+                                    if self.quirks.clip_sprites {
+                                        continue;
+                                    }
+                                    screen_x %= width;
+                                }
+
+                                // Questo u8 mi dice se il pixel di questa riga corrente dello sprite deve essere disegnato oppure no
+                                // Per esempio, il primo bit (da sx a dx) shiftato a destra di 7 va a finire nella prima posizione.
+                                // 10110000 >> 7 => 00000001 & 11111111 => 1. Il primo pixel della riga va acceso.
+                                // 10110000 >> 7 - 1 (processiamo il secondo bit significativo) => 00000000 & 11111111 => 0. Il secondo pixel va spento.
+                                let sprite_pixel = (sprite_byte >> (7 - col)) & 1;
+
+                                if sprite_pixel == 1 {
+                                    if self.display[screen_y][screen_x] & plane != 0 {
+                                        collided = true;
+                                    }
+                                    self.display[screen_y][screen_x] ^= plane;
+                                    self.mark_dirty(screen_x, screen_y);
+                                }
                             }
-                            self.display[screen_y][screen_x] ^= true;
+                        }
+
+                        if collided {
+                            collided_rows += 1;
                         }
                     }
                 }
 
+                // Collision flag. SUPER-CHIP in high resolution counts the rows that
+                // collided or were clipped at the bottom, everything else just sets it to 1.
+                self.v[0xF] = if self.variant == Variant::SuperChip && self.hires {
+                    collided_rows + clipped_rows
+                } else {
+                    u8::from(collided_rows > 0)
+                };
+
                 // Signaling display should be updated
                 self.update_display = true;
+                // The VIP always waited for vblank before drawing
+                self.waiting_for_vblank =
+                    self.quirks.display_wait || self.timing == Timing::CosmacVip;
             }
 
             // Bitwise AND between 2 registers
-            Instruction::AND(x, y) => self.v[x] = self.v[x] & self.v[y],
+            Instruction::AND(x, y) => {
+                self.v[x] &= self.v[y];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
+            }
 
             // Bitwise OR between 2 registers
-            Instruction::OR(x, y) => self.v[x] = self.v[x] | self.v[y],
+            Instruction::OR(x, y) => {
+                self.v[x] |= self.v[y];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
+            }
 
             // Bitwise XOR between 2 registers
-            Instruction::XOR(x, y) => self.v[x] = self.v[x] ^ self.v[y],
+            Instruction::XOR(x, y) => {
+                self.v[x] ^= self.v[y];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
+                }
+            }
 
             // GEnerate random number, AND with nn, save in v[x]
             Instruction::Random(x, nn) => {
-                let mut rng = rand::rng();
-                self.v[x] = rng.random::<u8>() & nn;
+                self.v[x] = self.rng.next_u8() & nn;
             }
 
             // Skip next instruction if v[x] == nn
             Instruction::SEQ(x, nn) => {
                 if self.v[x] == nn {
-                    self.program_counter += 2;
+                    self.skip_next();
                 }
             }
 
             // Same as SEQ, but not equal
             Instruction::SNEQ(x, nn) => {
                 if self.v[x] != nn {
-                    self.program_counter += 2;
+                    self.skip_next();
                 }
             }
 
             // Same as SEQ, but with registers contents
             Instruction::SEQR(x, y) => {
                 if self.v[x] == self.v[y] {
-                    self.program_counter += 2;
+                    self.skip_next();
                 }
             }
 
             // Same as SNEQ, with registers
             Instruction::SNEQR(x, y) => {
                 if self.v[x] != self.v[y] {
-                    self.program_counter += 2;
+                    self.skip_next();
                 }
             }
 
@@ -309,9 +692,9 @@ impl Chip8 {
             Instruction::AddRegister(x, y) => {
                 let (result, overflow) = self.v[x].overflowing_add(self.v[y]);
 
-                self.v[0xF] = if overflow { 1 } else { 0 };
-
+                // The flag goes in last, so it wins when x is F
                 self.v[x] = result;
+                self.v[0xF] = if overflow { 1 } else { 0 };
             }
 
             // Gets the delay timer
@@ -332,15 +715,27 @@ impl Chip8 {
             // Calls subroutine at memory location nnn.
             // Pushes current program counter value to the stack, sets program counter to nnn.
             Instruction::Call(nnn) => {
+                if self.sp >= STACK_SIZE {
+                    return Err(Chip8Error::StackOverflow {
+                        pc: self.current_pc(),
+                    });
+                }
+
                 // Push current PC to stack
                 self.stack[self.sp] = self.program_counter;
                 self.sp += 1;
                 // Jump to subroutine
-                self.program_counter = nnn;
+                self.program_counter = self.wrap_address(nnn);
             }
 
             // Pops the stack and returns from whence it came
             Instruction::Return => {
+                if self.sp == 0 {
+                    return Err(Chip8Error::StackUnderflow {
+                        pc: self.current_pc(),
+                    });
+                }
+
                 // Pop PC from stack
                 self.sp -= 1;
                 self.program_counter = self.stack[self.sp];
@@ -350,17 +745,22 @@ impl Chip8 {
             Instruction::AddToIndex(x) => self.i = self.i.wrapping_add(self.v[x] as u16),
 
             // Stores what's in registers from 0 to x included and loades them in memory, at locations i + j.
+            // On the COSMAC VIP, i is left pointing right after the last location written.
             Instruction::StoreMemory(x) => {
-                for j in 0..=x {
-                    self.memory[self.i as usize + j] = self.v[j];
+                let range = self.memory_range(self.i as usize, x + 1)?;
+                self.last_write = Some(range.clone());
+                self.memory[range].copy_from_slice(&self.v[0..=x]);
+                if self.quirks.memory_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
                 }
             }
 
             // Same as before.
-            // TODO: Quirk
             Instruction::LoadMemory(x) => {
-                for i in 0..=x {
-                    self.v[i] = self.memory[self.i as usize + i];
+                let range = self.memory_range(self.i as usize, x + 1)?;
+                self.v[0..=x].copy_from_slice(&self.memory[range]);
+                if self.quirks.memory_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
                 }
             }
 
@@ -369,99 +769,129 @@ impl Chip8 {
                 self.i = FONT_MEMORY_START as u16 + (self.v[x] as u16 * 5);
             }
 
+            // XO-CHIP: Sets the index register to the 16 bit address following the instruction.
+            Instruction::LongIndex => {
+                self.i = self.read_word(self.program_counter);
+                self.program_counter = self.wrap_address(self.program_counter.wrapping_add(2));
+            }
+
+            // XO-CHIP: Stores v[x] to v[y] in memory starting at i, in either direction. i doesn't change.
+            Instruction::StoreRange(x, y) => {
+                let registers = register_range(x, y);
+                let range = self.memory_range(self.i as usize, registers.len())?;
+                self.last_write = Some(range.clone());
+                for (address, register) in range.zip(registers) {
+                    self.memory[address] = self.v[register];
+                }
+            }
+
+            // XO-CHIP: Loads v[x] to v[y] from memory starting at i.
+            Instruction::LoadRange(x, y) => {
+                let registers = register_range(x, y);
+                let range = self.memory_range(self.i as usize, registers.len())?;
+                for (address, register) in range.zip(registers) {
+                    self.v[register] = self.memory[address];
+                }
+            }
+
+            // XO-CHIP: Selects the bit-planes the display instructions work on.
+            Instruction::SelectPlanes(n) => self.planes = n & 0b11,
+
+            // XO-CHIP: Loads 16 bytes at i into the audio pattern buffer.
+            Instruction::LoadAudioPattern => {
+                let range = self.memory_range(self.i as usize, 16)?;
+                self.audio_pattern.copy_from_slice(&self.memory[range]);
+            }
+
+            // XO-CHIP: Sets the audio playback pitch.
+            Instruction::SetPitch(x) => self.pitch = self.v[x],
+
+            // Same as GetFontCharacter, with the SUPER-CHIP 8x10 font.
+            Instruction::GetBigFontCharacter(x) => {
+                self.i = BIG_FONT_MEMORY_START as u16 + ((self.v[x] & 0xF) as u16 * 10);
+            }
+
+            // Saves v[0] to v[x] in the RPL user flags.
+            Instruction::StoreFlags(x) => self.rpl[0..=x].copy_from_slice(&self.v[0..=x]),
+
+            // Restores v[0] to v[x] from the RPL user flags.
+            Instruction::LoadFlags(x) => self.v[0..=x].copy_from_slice(&self.rpl[0..=x]),
+
             // Converts binary to decimal, naive.
             Instruction::BinaryToDecimal(x) => {
                 let to_convert = self.v[x];
-                self.memory[self.i as usize] = to_convert / 100;
-                self.memory[self.i as usize + 1] = (to_convert / 10) % 10;
-                self.memory[self.i as usize + 2] = to_convert % 10;
+                let range = self.memory_range(self.i as usize, 3)?;
+                self.last_write = Some(range.clone());
+                self.memory[range].copy_from_slice(&[
+                    to_convert / 100,
+                    (to_convert / 10) % 10,
+                    to_convert % 10,
+                ]);
             }
 
             // Lshift shifts the contents of v[x] to v[y], shifts it to the right and saves the shifted bit to v[f].
-            // TODO: Quirk
+            // Without the shift_uses_vy quirk, v[x] is shifted in place and v[y] is ignored.
             Instruction::LShift(x, y) => {
-                let bit = (self.v[y] & 0x80) >> 7;
-                self.v[x] = self.v[y] << 1;
+                let source = if self.quirks.shift_uses_vy { y } else { x };
+                let bit = (self.v[source] & 0x80) >> 7;
+                self.v[x] = self.v[source] << 1;
                 self.v[0xF] = bit;
             }
 
-            // TODO: Quirk
             Instruction::RShift(x, y) => {
-                let bit = self.v[y] & 1;
-                self.v[x] = self.v[y] >> 1;
+                let source = if self.quirks.shift_uses_vy { y } else { x };
+                let bit = self.v[source] & 1;
+                self.v[x] = self.v[source] >> 1;
                 self.v[0xF] = bit;
             }
 
             Instruction::SkipIfKey(x) => {
                 let key = self.v[x] & 0xF;
                 if key < 16 && self.keyboard[key as usize] {
-                    self.program_counter += 2;
+                    self.skip_next();
                 }
             }
 
             Instruction::SkipIfNotKey(x) => {
                 let key = self.v[x] & 0xF;
                 if key < 16 && !self.keyboard[key as usize] {
-                    self.program_counter += 2;
+                    self.skip_next();
                 }
             }
 
+            // Like on the COSMAC VIP the key counts once it's released, not when it's pressed.
             Instruction::GetKey(x) => {
-                let mut key_found = None;
-                for (key_index, &pressed) in self.keyboard.iter().enumerate() {
-                    if pressed {
-                        key_found = Some(key_index as u8);
-                        break;
+                match self.held_key {
+                    Some(key) if !self.keyboard[key as usize] => {
+                        // Tasto rilasciato - salvalo e continua
+                        self.v[x] = key;
+                        self.waiting_for_key = None;
+                        self.held_key = None;
+                    }
+                    _ => {
+                        if self.held_key.is_none() {
+                            self.held_key = self
+                                .keyboard
+                                .iter()
+                                .position(|&pressed| pressed)
+                                .map(|key| key as u8);
+                        }
+                        // Nessun tasto rilasciato - aspetta
+                        self.waiting_for_key = Some(x);
+                        self.program_counter = self.current_pc(); // Ripeti questa istruzione
                     }
-                }
-
-                if let Some(key) = key_found {
-                    // Tasto trovato - salvalo e continua
-                    self.v[x] = key;
-                    self.waiting_for_key = None;
-                } else {
-                    // Nessun tasto premuto - aspetta
-                    self.waiting_for_key = Some(x);
-                    self.program_counter -= 2; // Ripeti questa istruzione
                 }
             }
         }
         Ok(())
     }
 
-    pub fn print_filled_memory(&self) {
-        println!(
-            "{:#?}",
-            self.memory
-                .iter()
-                .enumerate()
-                .filter(|(_, x)| **x != 0)
-                .collect::<Vec<_>>()
-        )
-    }
-
-    pub fn print_display(&self) {
-        print!("\x1B[2J\x1B[1;1H");
-
-        for row in &self.display {
-            for &pixel in row {
-                if pixel {
-                    print!("██");
-                } else {
-                    print!("  ");
-                }
-            }
-            println!();
-        }
-        println!();
-    }
-
     // Esegue N cicli di CPU (ticks)
-    pub fn run(&mut self, ticks: usize) -> Result<(), String> {
+    pub fn run(&mut self, ticks: usize) -> Result<(), Chip8Error> {
         for _ in 0..ticks {
-            let opcode = self.fetch();
-            let instruction = self.decode(opcode)?;
-            self.execute(instruction)?;
+            if !self.step()? {
+                break;
+            }
 
             // Se stiamo aspettando un tasto, ferma l'esecuzione
             if self.waiting_for_key.is_some() {
@@ -471,16 +901,172 @@ impl Chip8 {
         Ok(())
     }
 
+    // Runs a single instruction: fetch, decode, execute.
+    // Returns false without doing anything if the machine can't run right now.
+    pub fn step(&mut self) -> Result<bool, Chip8Error> {
+        // Display wait quirk: nothing runs until the next vblank (timer tick).
+        // After a SUPER-CHIP exit nothing runs at all.
+        if self.waiting_for_vblank || self.halted {
+            return Ok(false);
+        }
+
+        self.last_write = None;
+        match self.tracer.take() {
+            Some(mut tracer) => {
+                let result = tracer.step(self);
+                self.tracer = Some(tracer);
+                result?;
+            }
+            None => {
+                self.execute_next()?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn execute_next(&mut self) -> Result<Instruction, Chip8Error> {
+        let opcode = self.fetch();
+        let instruction = self.decode(opcode)?;
+        let cycles = self.instruction_cycles(instruction);
+        self.execute(instruction)?;
+        self.spend_cycles(cycles);
+        Ok(instruction)
+    }
+
+    // Logs every instruction step runs from now on.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Chip8 {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    // Detaches the tracer, to flush it or look at its cycle count.
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
     // Aggiorna i timer (chiamato separatamente a 60Hz), dal chiamante
     pub fn tick_timers(&mut self) {
+        self.waiting_for_vblank = false;
+        self.start_frame_cycles();
         if self.delay > 0 {
             self.delay -= 1;
         }
+        self.play_audio_frame();
         if self.sound > 0 {
             self.sound -= 1;
         }
     }
 
+    // Read-only view of the screen, for renderers.
+    pub fn framebuffer(&self) -> Framebuffer<'_> {
+        Framebuffer::new(self.display_width(), self.display_height(), &self.display)
+    }
+
+    // What Display, Clear and scrolling touched since the last call, None if nothing did.
+    // Renderers can use it to redraw only the rows or the rectangle that changed.
+    pub fn take_dirty_region(&mut self) -> Option<DirtyRegion> {
+        self.dirty.take()
+    }
+
+    // Same as take_dirty_region, without resetting it.
+    pub fn dirty_region(&self) -> Option<DirtyRegion> {
+        self.dirty
+    }
+
+    // Current resolution, 64x32 or 128x64 in SUPER-CHIP high resolution mode.
+    pub fn display_width(&self) -> usize {
+        if self.hires {
+            HIRES_DISPLAY_SIZE_X
+        } else {
+            DISPLAY_SIZE_X_KB
+        }
+    }
+
+    pub fn display_height(&self) -> usize {
+        if self.hires {
+            HIRES_DISPLAY_SIZE_Y
+        } else {
+            DISPLAY_SIZE_Y_KB
+        }
+    }
+
+    // XO-CHIP audio pattern buffer, 128 one bit samples played while the sound timer runs.
+    pub fn audio_pattern(&self) -> &[u8; 16] {
+        &self.audio_pattern
+    }
+
+    // Playback rate of the audio pattern is 4000 * 2^((pitch - 64) / 48) Hz.
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    // Address of the next instruction to run.
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    // The index register, I.
+    pub fn index(&self) -> u16 {
+        self.i
+    }
+
+    // V0 to VF.
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.v
+    }
+
+    // Return addresses of the calls in progress, the innermost last.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.sp]
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound
+    }
+
+    // The whole address space of the current variant.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory[..self.memory_size()]
+    }
+
+    // Opcode at address, as fetch would read it.
+    pub fn opcode_at(&self, address: u16) -> u16 {
+        self.read_word(address)
+    }
+
+    // The register FX0A will store the key in, while it waits for one.
+    pub fn waiting_for_key(&self) -> Option<usize> {
+        self.waiting_for_key
+    }
+
+    // Keys 0-F, anything above wraps around.
+    pub fn key_down(&mut self, key: u8) {
+        self.keyboard[(key & 0xF) as usize] = true;
+    }
+
+    // A key that goes down and up between two instructions still ends an FX0A wait.
+    pub fn key_up(&mut self, key: u8) {
+        let key = key & 0xF;
+        if self.waiting_for_key.is_some() && self.keyboard[key as usize] {
+            self.held_key = Some(key);
+        }
+        self.keyboard[key as usize] = false;
+    }
+
+    // True while the display wait quirk holds execution until the next timer tick.
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        self.waiting_for_vblank
+    }
+
+    // True once the ROM executed the SUPER-CHIP exit instruction.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
     pub fn should_update_display(&mut self) -> bool {
         if self.update_display {
             self.update_display = false;
@@ -491,42 +1077,11 @@ impl Chip8 {
     }
 }
 
-
-// Contiene tutte l'instruction set.
-#[derive(Debug)]
-enum Instruction {
-    Clear,
-    Jump(u16),
-    JumpOffset(u16),
-    Call(u16),
-    Return,
-    SEQ(usize, u8),
-    SNEQ(usize, u8),
-    SEQR(usize, usize),
-    SNEQR(usize, usize),
-    Set(usize, u8),
-    SetRegister(usize, usize),
-    OR(usize, usize),
-    AND(usize, usize),
-    XOR(usize, usize),
-    Add(usize, u8),
-    AddRegister(usize, usize),
-    Subtract(usize, usize),
-    SubtractInv(usize, usize),
-    Random(usize, u8),
-    LShift(usize, usize),
-    RShift(usize, usize),
-    SkipIfKey(usize),
-    SkipIfNotKey(usize),
-    GetDelayTimer(usize),
-    SetDelayTimer(usize),
-    SetSoundTimer(usize),
-    AddToIndex(usize),
-    GetKey(usize),
-    GetFontCharacter(usize),
-    BinaryToDecimal(usize),
-    StoreMemory(usize),
-    LoadMemory(usize),
-    SetIndex(u16),
-    Display(usize, usize, u8),
+// Registers from x to y included, counting down when y comes before x.
+fn register_range(x: usize, y: usize) -> Vec<usize> {
+    if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    }
 }
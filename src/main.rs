@@ -1,15 +1,54 @@
-use crossterm::{
-    event::{Event, KeyCode, poll, read},
-    terminal::{disable_raw_mode, enable_raw_mode},
-};
 use std::env;
-use std::thread;
-use std::time::{Duration, Instant};
-use rust_8::Chip8;
+use rust_8::{
+    Chip8, DEFAULT_REWIND_INTERVAL, Quirks, Timing, Tracer, Variant,
+    audio::{DEFAULT_TONE, DEFAULT_VOLUME, WavSink},
+    octo::Compiler,
+    render::parse_color,
+};
+
+mod commands;
+mod frontend;
+
+use frontend::{Frontend, headless::ScriptedKey};
 
 struct Config {
     rom_path: String,
     cpu_freq: u32,
+    // None means the variant's usual quirks
+    quirks: Option<Quirks>,
+    variant: Variant,
+    // COSMAC VIP timing replaces cpu_freq with the real cost of every instruction
+    timing: Timing,
+    entry_point: u16,
+    // None picks a random seed every run
+    seed: Option<u64>,
+    frontend: Frontend,
+    // Window frontend look: colors for unlit and lit pixels, and the starting size
+    background: Option<(u8, u8, u8)>,
+    foreground: Option<(u8, u8, u8)>,
+    scale: u32,
+    // Start the debugger prompt instead of a frontend
+    debug: bool,
+    // Instruction log, optionally only for some addresses or only the last lines before an error
+    trace: Option<String>,
+    trace_range: Option<(u16, u16)>,
+    trace_last: Option<usize>,
+    // Sound: recorded to a WAV file, or the terminal bell
+    wav: Option<String>,
+    bell: bool,
+    tone: f32,
+    // From 0 to 1
+    volume: f32,
+    // Instructions per frame, overrides cpu_freq
+    cycles_per_frame: Option<usize>,
+    // Headless runs: how long, which keys get pressed and where the final screen goes
+    headless: bool,
+    frames: Option<u64>,
+    keys: Vec<ScriptedKey>,
+    screen: Option<String>,
+    // Rewind history: how far back, and frames between snapshots. 0 seconds turns it off
+    rewind_seconds: u32,
+    rewind_interval: u32,
 }
 
 impl Config {
@@ -18,8 +57,33 @@ impl Config {
         
         let mut rom_path = String::from("test_roms\\tetris.ch8");
         let mut cpu_freq = 700;
+        let mut quirks = None;
+        let mut variant = Variant::default();
+        let mut timing = Timing::default();
+        let mut entry_point = 0x200;
+        let mut seed = None;
+        let mut frontend = Frontend::Terminal;
+        let mut background = None;
+        let mut foreground = None;
+        let mut scale = 10;
+        let mut debug = false;
+        let mut trace = None;
+        let mut trace_range = None;
+        let mut trace_last = None;
+        let mut wav = None;
+        let mut bell = false;
+        let mut tone = DEFAULT_TONE;
+        let mut volume = DEFAULT_VOLUME;
+        let mut headless = false;
+        let mut frames = None;
+        let mut cycles_per_frame = None;
+        let mut keys = Vec::new();
+        let mut screen = None;
+        let mut rewind_seconds = 60;
+        let mut rewind_interval = DEFAULT_REWIND_INTERVAL;
         
-        let mut i = 1;
+        // `rust-8 run ...` is the same as `rust-8 ...`
+        let mut i = if args.get(1).map(String::as_str) == Some("run") { 2 } else { 1 };
         while i < args.len() {
             match args[i].as_str() {
                 "--rom" => {
@@ -39,6 +103,230 @@ impl Config {
                         return Err("--tickcpu requires a frequency value".to_string());
                     }
                 }
+                "--quirks" => {
+                    if i + 1 < args.len() {
+                        quirks = Some(Quirks::preset(&args[i + 1]).ok_or_else(|| {
+                            format!(
+                                "Unknown quirk preset '{}', expected one of: {}",
+                                args[i + 1],
+                                Quirks::PRESET_NAMES.join(", ")
+                            )
+                        })?);
+                        i += 2;
+                    } else {
+                        return Err("--quirks requires a preset name".to_string());
+                    }
+                }
+                "--timing" => {
+                    if i + 1 < args.len() {
+                        timing = Timing::from_name(&args[i + 1]).ok_or_else(|| {
+                            format!(
+                                "Unknown timing '{}', expected one of: {}",
+                                args[i + 1],
+                                Timing::NAMES.join(", ")
+                            )
+                        })?;
+                        i += 2;
+                    } else {
+                        return Err("--timing requires fixed or vip".to_string());
+                    }
+                }
+                "--variant" => {
+                    if i + 1 < args.len() {
+                        variant = Variant::from_name(&args[i + 1]).ok_or_else(|| {
+                            format!(
+                                "Unknown variant '{}', expected one of: {}",
+                                args[i + 1],
+                                Variant::NAMES.join(", ")
+                            )
+                        })?;
+                        i += 2;
+                    } else {
+                        return Err("--variant requires a variant name".to_string());
+                    }
+                }
+                "--entry" => {
+                    if i + 1 < args.len() {
+                        entry_point = parse_address(&args[i + 1])
+                            .ok_or_else(|| "Invalid entry point address".to_string())?;
+                        i += 2;
+                    } else {
+                        return Err("--entry requires an address".to_string());
+                    }
+                }
+                "--seed" => {
+                    if i + 1 < args.len() {
+                        seed = Some(args[i + 1].parse()
+                            .map_err(|_| "Invalid seed, expected a number".to_string())?);
+                        i += 2;
+                    } else {
+                        return Err("--seed requires a number".to_string());
+                    }
+                }
+                "--frontend" => {
+                    if i + 1 < args.len() {
+                        frontend = Frontend::from_name(&args[i + 1]).ok_or_else(|| {
+                            format!(
+                                "Unknown frontend '{}', expected terminal or window",
+                                args[i + 1]
+                            )
+                        })?;
+                        i += 2;
+                    } else {
+                        return Err("--frontend requires terminal or window".to_string());
+                    }
+                }
+                "--bg" | "--fg" => {
+                    if i + 1 < args.len() {
+                        let color = parse_color(&args[i + 1]).ok_or_else(|| {
+                            format!("Invalid color '{}', expected RRGGBB", args[i + 1])
+                        })?;
+                        if args[i] == "--bg" {
+                            background = Some(color);
+                        } else {
+                            foreground = Some(color);
+                        }
+                        i += 2;
+                    } else {
+                        return Err(format!("{} requires a color", args[i]));
+                    }
+                }
+                "--scale" => {
+                    if i + 1 < args.len() {
+                        scale = args[i + 1].parse().ok().filter(|&scale| scale > 0)
+                            .ok_or_else(|| "Invalid scale, expected a whole number".to_string())?;
+                        i += 2;
+                    } else {
+                        return Err("--scale requires a number".to_string());
+                    }
+                }
+                "--debug" => {
+                    debug = true;
+                    i += 1;
+                }
+                "--trace" => {
+                    if i + 1 < args.len() {
+                        trace = Some(args[i + 1].clone());
+                        i += 2;
+                    } else {
+                        return Err("--trace requires a file path".to_string());
+                    }
+                }
+                "--trace-range" => {
+                    if i + 1 < args.len() {
+                        let range = args[i + 1].split_once('-').and_then(|(start, end)| {
+                            Some((parse_address(start)?, parse_address(end)?))
+                        });
+                        trace_range = Some(range.ok_or_else(|| {
+                            "Invalid trace range, expected START-END".to_string()
+                        })?);
+                        i += 2;
+                    } else {
+                        return Err("--trace-range requires START-END".to_string());
+                    }
+                }
+                "--trace-last" => {
+                    if i + 1 < args.len() {
+                        trace_last = Some(args[i + 1].parse()
+                            .map_err(|_| "Invalid line count for --trace-last".to_string())?);
+                        i += 2;
+                    } else {
+                        return Err("--trace-last requires a line count".to_string());
+                    }
+                }
+                "--wav" => {
+                    if i + 1 < args.len() {
+                        wav = Some(args[i + 1].clone());
+                        i += 2;
+                    } else {
+                        return Err("--wav requires a file path".to_string());
+                    }
+                }
+                "--bell" => {
+                    bell = true;
+                    i += 1;
+                }
+                "--tone" => {
+                    if i + 1 < args.len() {
+                        tone = args[i + 1].parse().ok().filter(|&hz: &f32| hz >= 1.0)
+                            .ok_or_else(|| "Invalid tone, expected a frequency in Hz".to_string())?;
+                        i += 2;
+                    } else {
+                        return Err("--tone requires a frequency".to_string());
+                    }
+                }
+                "--volume" => {
+                    if i + 1 < args.len() {
+                        let percent: u8 = args[i + 1].parse().ok().filter(|&percent| percent <= 100)
+                            .ok_or_else(|| "Invalid volume, expected 0 to 100".to_string())?;
+                        volume = percent as f32 / 100.0;
+                        i += 2;
+                    } else {
+                        return Err("--volume requires a percentage".to_string());
+                    }
+                }
+                "--headless" => {
+                    headless = true;
+                    i += 1;
+                }
+                "--frames" => {
+                    if i + 1 < args.len() {
+                        frames = Some(args[i + 1].parse()
+                            .map_err(|_| "Invalid frame count for --frames".to_string())?);
+                        i += 2;
+                    } else {
+                        return Err("--frames requires a frame count".to_string());
+                    }
+                }
+                "--cycles-per-frame" => {
+                    if i + 1 < args.len() {
+                        cycles_per_frame = Some(args[i + 1].parse().ok().filter(|&cycles| cycles > 0)
+                            .ok_or_else(|| "Invalid cycle count for --cycles-per-frame".to_string())?);
+                        i += 2;
+                    } else {
+                        return Err("--cycles-per-frame requires a number".to_string());
+                    }
+                }
+                "--keys" => {
+                    if i + 1 < args.len() {
+                        // @FILE reads the script from a file
+                        let script = match args[i + 1].strip_prefix('@') {
+                            Some(path) => std::fs::read_to_string(path)
+                                .map_err(|e| format!("Can't read key script {}: {}", path, e))?,
+                            None => args[i + 1].clone(),
+                        };
+                        keys.extend(frontend::headless::parse_key_script(&script)?);
+                        i += 2;
+                    } else {
+                        return Err("--keys requires FRAME:KEY[:HOLD] entries or @FILE".to_string());
+                    }
+                }
+                "--screen" => {
+                    if i + 1 < args.len() {
+                        screen = Some(args[i + 1].clone());
+                        i += 2;
+                    } else {
+                        return Err("--screen requires a file path, or - for stdout".to_string());
+                    }
+                }
+                "--rewind" => {
+                    if i + 1 < args.len() {
+                        rewind_seconds = args[i + 1].parse()
+                            .map_err(|_| "Invalid length for --rewind, expected seconds".to_string())?;
+                        i += 2;
+                    } else {
+                        return Err("--rewind requires a number of seconds".to_string());
+                    }
+                }
+                "--rewind-every" => {
+                    if i + 1 < args.len() {
+                        rewind_interval = args[i + 1].parse().ok().filter(|&frames| frames > 0)
+                            .ok_or_else(|| "Invalid frame count for --rewind-every".to_string())?;
+                        i += 2;
+                    } else {
+                        return Err("--rewind-every requires a frame count".to_string());
+                    }
+                }
                 "--help" | "-h" => {
                     print_help();
                     std::process::exit(0);
@@ -52,25 +340,109 @@ impl Config {
                 }
             }
         }
+
+        if headless && frames.is_none() {
+            return Err("--headless requires --frames N".to_string());
+        }
         
-        Ok(Config { rom_path, cpu_freq })
+        Ok(Config {
+            rom_path,
+            cpu_freq,
+            quirks,
+            variant,
+            timing,
+            entry_point,
+            seed,
+            frontend,
+            background,
+            foreground,
+            scale,
+            debug,
+            trace,
+            trace_range,
+            trace_last,
+            wav,
+            bell,
+            tone,
+            volume,
+            headless,
+            frames,
+            cycles_per_frame,
+            keys,
+            screen,
+            rewind_seconds,
+            rewind_interval,
+        })
     }
 }
 
+// Accepts addresses as 0x600, 600h or plain decimal.
+fn parse_address(text: &str) -> Option<u16> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(hex) = text.strip_suffix('h') {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        text.parse().ok()
+    }
+}
+
+fn is_octo_source(path: &str) -> bool {
+    path.to_ascii_lowercase().ends_with(".8o")
+}
+
 fn print_help() {
     println!("CHIP-8 Emulator");
     println!("Usage: cargo run [OPTIONS] [ROM_PATH]");
+    println!("       cargo run disasm ROM_PATH [--syntax octo|classic] [--variant NAME] [--entry ADDR]");
+    println!("       cargo run asm SOURCE [-o OUT.ch8] [--variant NAME] [--entry ADDR]");
+    println!("       cargo run run --headless --frames N [OPTIONS] ROM_PATH");
+    println!("       (SOURCE and ROM_PATH ending in .8o are compiled as Octo)");
     println!();
     println!("OPTIONS:");
     println!("  --rom <PATH>     ROM file to load (default: test_roms\\tetris.ch8)");
     println!("  --tickcpu, --cpu <FREQ>     CPU frequency in Hz (default: 700)");
+    println!("  --variant <NAME>            Instruction set: chip8, schip, xochip (default: chip8)");
+    println!("  --quirks <PRESET>           Interpreter quirks: legacy, vip, chip48, schip, xochip (default: the variant's)");
+    println!("  --cycles-per-frame <N>      Instructions per 60Hz frame, instead of --cpu (default: CPU frequency / 60)");
+    println!("  --timing <fixed|vip>        fixed runs --cpu instructions a second, vip gives every instruction its COSMAC VIP cost");
+    println!("  --entry <ADDR>              Load address and entry point (default: 0x200, ETI-660: 0x600)");
+    println!("  --seed <N>                  Seed for the random number generator, for reproducible runs");
+    println!("  --frontend <NAME>           Display: terminal, window (default: terminal)");
+    println!("  --fg, --bg <RRGGBB>         Window colors for lit and unlit pixels (default: FFFFFF, 000000)");
+    println!("  --scale <N>                 Starting window size, in window pixels per CHIP-8 pixel (default: 10)");
+    println!("  --debug                     Start the debugger prompt instead of running the ROM");
+    println!("  --trace <FILE>              Log every executed instruction and the registers it changed");
+    println!("  --trace-range <START-END>   Only log instructions at these addresses (e.g. 0x200-0x2FF)");
+    println!("  --trace-last <N>            Only write the last N instructions, when the CPU stops on an error");
+    println!("  --wav <FILE>                Record the sound to a WAV file");
+    println!("  --bell                      Ring the terminal bell when the sound timer starts");
+    println!("  --tone <HZ>                 Beeper frequency for --wav (default: 440)");
+    println!("  --volume <0-100>            Volume for --wav (default: 50)");
+    println!("  --rewind <SECONDS>          How far back Backspace can rewind, 0 turns it off (default: 60)");
+    println!("  --rewind-every <FRAMES>     Frames between rewind snapshots (default: 10)");
     println!("  --help, -h                  Show this help message");
     println!();
+    println!("HEADLESS:");
+    println!("  --headless                  Run without a display, as fast as possible, then exit");
+    println!("  --frames <N>                Number of 60Hz frames to run (required)");
+    println!("  --keys <SCRIPT|@FILE>       Key presses as FRAME:KEY[:HOLD], e.g. \"30:5 90:A:10\"");
+    println!("  --screen <FILE|->           Write the final screen as text, or PNG if FILE ends in .png");
+    println!("  Exits with status 1 if the CPU stops on an error.");
+    println!();
     println!("EXAMPLES:");
     println!("  cargo run                                    # Run with default ROM and settings");
     println!("  cargo run my_game.ch8                       # Run specific ROM");
     println!("  cargo run --nomerom pong.ch8 --tickcpu 1000 # Run with custom ROM and CPU speed");
     println!("  cargo run --cpu 500                         # Run with slower CPU");
+    println!("  cargo run --variant schip game.ch8          # Run a SUPER-CHIP game");
+    println!("  cargo run --quirks chip48 game.ch8          # Run with CHIP-48 behavior");
+    println!("  cargo run --timing vip game.ch8             # Run at the speed of the original hardware");
+    println!("  cargo run --frontend window game.ch8        # Run in a window");
+    println!("  cargo run --debug game.ch8                  # Step through a ROM");
+    println!("  cargo run run --headless --frames 60 --screen - game.ch8  # Print the screen after a second");
+    println!("  cargo run --variant xochip game.8o          # Compile and run an Octo program");
+    println!("  cargo run --wav sound.wav game.ch8          # Record what the game plays");
     println!();
     println!("KEYBOARD LAYOUT:");
     println!("  CHIP-8:     Keyboard:");
@@ -79,94 +451,101 @@ fn print_help() {
     println!("  7 8 9 E     A S D F");
     println!("  A 0 B F     Z X C V");
     println!();
+    println!("WINDOW:");
+    println!("  P pauses and resumes. The screen scales by whole factors, resizing keeps the aspect ratio.");
+    println!();
+    println!("SAVE STATES (terminal frontend):");
+    println!("  F1-F4 save to slot 1-4, F5-F8 load slot 1-4");
+    println!();
+    println!("REWIND:");
+    println!("  Hold Backspace to step back through the last --rewind seconds, in both frontends");
+    println!();
     println!("Press ESC to exit the emulator.");
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("disasm") => return commands::disasm::run(&args[2..]),
+        Some("asm") => return commands::asm::run(&args[2..]),
+        _ => {}
+    }
+
     let config = Config::from_args().map_err(|e| {
         eprintln!("Error: {}", e);
         eprintln!("Use --help for usage information.");
         std::process::exit(1);
     })?;
     
-    println!("Loading ROM: {}", config.rom_path);
-    println!("CPU Frequency: {} Hz", config.cpu_freq);
-    
-    let mut chip8 = Chip8::new().load_rom(&config.rom_path)?;
-    
-    enable_raw_mode()?;
-    
-    let mut last_cpu_time = Instant::now();
-    let mut last_timer_time = Instant::now();
-    let mut last_display_time = Instant::now();
-    
-    let cpu_freq = Duration::from_nanos(1_000_000_000 / config.cpu_freq as u64);
-    let timer_freq = Duration::from_nanos(1_000_000_000 / 60); // 60Hz timers
-    let display_freq = Duration::from_millis(16); // ~60 FPS display
-    
-    println!("Starting emulator... Press ESC to exit.");
-    
-    'main: loop {
-        let frame_start = Instant::now();
-        
-        // 1. Reset keyboard every frame
-        chip8.keyboard.fill(false);
-        
-        // 2. Handle input events
-        while poll(Duration::from_millis(1))? {
-            match read()? {
-                Event::Key(key_event) => match key_event.code {
-                    KeyCode::Esc => break 'main,
-                    KeyCode::Char('1') => chip8.keyboard[0x1] = true,
-                    KeyCode::Char('2') => chip8.keyboard[0x2] = true,
-                    KeyCode::Char('3') => chip8.keyboard[0x3] = true,
-                    KeyCode::Char('4') => chip8.keyboard[0xC] = true,
-                    KeyCode::Char('q') => chip8.keyboard[0x4] = true,
-                    KeyCode::Char('w') => chip8.keyboard[0x5] = true,
-                    KeyCode::Char('e') => chip8.keyboard[0x6] = true,
-                    KeyCode::Char('r') => chip8.keyboard[0xD] = true,
-                    KeyCode::Char('a') => chip8.keyboard[0x7] = true,
-                    KeyCode::Char('s') => chip8.keyboard[0x8] = true,
-                    KeyCode::Char('d') => chip8.keyboard[0x9] = true,
-                    KeyCode::Char('f') => chip8.keyboard[0xE] = true,
-                    KeyCode::Char('z') => chip8.keyboard[0xA] = true,
-                    KeyCode::Char('x') => chip8.keyboard[0x0] = true,
-                    KeyCode::Char('c') => chip8.keyboard[0xB] = true,
-                    KeyCode::Char('v') => chip8.keyboard[0xF] = true,
-                    _ => {}
-                },
-                _ => {}
-            }
+    // Headless runs may print the screen, keep stdout clean for it
+    if !config.headless {
+        println!("Loading ROM: {}", config.rom_path);
+        match config.timing {
+            Timing::Fixed => println!("CPU Frequency: {} Hz", config.cpu_freq),
+            Timing::CosmacVip => println!("Timing: COSMAC VIP"),
         }
-        
-        if last_cpu_time.elapsed() >= cpu_freq {
-            let ticks = (last_cpu_time.elapsed().as_nanos() / cpu_freq.as_nanos()) as usize;
-            if let Err(e) = chip8.run(ticks.min(10)) { // Max 10 ticks per frame
-                eprintln!("CPU Error: {}", e);
-                break;
-            }
-            last_cpu_time = Instant::now();
+    }
+    
+    // --cpu is rounded to whole instructions per 60Hz frame
+    let cycles_per_frame = config
+        .cycles_per_frame
+        .unwrap_or((config.cpu_freq as usize + 30) / 60);
+    let mut chip8 = Chip8::new()
+        .with_variant(config.variant)
+        .with_timing(config.timing)
+        .with_cycles_per_frame(cycles_per_frame);
+    if let Some(quirks) = config.quirks {
+        chip8 = chip8.with_quirks(quirks);
+    }
+    if let Some(seed) = config.seed {
+        chip8 = chip8.with_seed(seed);
+    }
+    if let Some(path) = &config.trace {
+        let mut tracer = Tracer::to_file(path)
+            .map_err(|e| format!("Can't create trace file {}: {}", path, e))?;
+        if let Some((start, end)) = config.trace_range {
+            tracer = tracer.with_range(start, end);
         }
-        
-        if last_timer_time.elapsed() >= timer_freq {
-            chip8.tick_timers();
-            last_timer_time = Instant::now();
+        if let Some(lines) = config.trace_last {
+            tracer = tracer.with_ring_buffer(lines);
         }
-        
-        if last_display_time.elapsed() >= display_freq {
-            if chip8.should_update_display() {
-                chip8.print_display();
+        chip8 = chip8.with_tracer(tracer);
+    }
+    if let Some(path) = &config.wav {
+        let sink = WavSink::create(path)
+            .map_err(|e| format!("Can't create WAV file {}: {}", path, e))?
+            .with_tone(config.tone)
+            .with_volume(config.volume);
+        chip8 = chip8.with_audio_sink(sink);
+    } else if config.bell {
+        chip8 = chip8.with_audio_sink(frontend::Bell);
+    }
+    let chip8 = chip8.with_entry_point(config.entry_point);
+    // Octo sources get compiled for the configured variant on the way in
+    let chip8 = if is_octo_source(&config.rom_path) {
+        let compiler = Compiler::new()
+            .with_variant(config.variant)
+            .with_origin(config.entry_point);
+        match compiler.compile_file(&config.rom_path) {
+            Ok(image) => chip8.load_rom_bytes(&image)?,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
             }
-            last_display_time = Instant::now();
-        }
-        
-        let elapsed = frame_start.elapsed();
-        if elapsed < Duration::from_millis(1) {
-            thread::sleep(Duration::from_millis(1) - elapsed);
         }
+    } else {
+        chip8.load_rom(&config.rom_path)?
+    };
+
+    if config.debug {
+        return frontend::debug::run(chip8, &config);
     }
-    
-    disable_raw_mode()?;
-    println!("Emulator stopped.");
-    Ok(())
-}
\ No newline at end of file
+    if config.headless {
+        return frontend::headless::run(chip8, &config);
+    }
+
+    match config.frontend {
+        Frontend::Terminal => frontend::terminal::run(chip8, &config),
+        Frontend::Window => frontend::window::run(chip8, &config),
+    }
+}
@@ -0,0 +1,1052 @@
+// Compiler for Octo, the language of the Octo IDE, so programs written for it build
+// and run here without other tools. Like the classic assembler, instructions are checked
+// against the decoder of the target variant.
+//
+//     # comments start with a hash
+//     :const SPEED 2
+//     :alias x v0
+//     : main
+//         i := ball
+//         loop
+//             sprite x v1 4
+//             x += SPEED
+//             if x == 60 then x := 0
+//         again
+//     : ball 0x60 0xF0 0xF0 0x60
+//
+// Execution starts at `: main`. When it isn't the first label the image starts with a jump to it.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    Variant,
+    asm::{AsmError, address, byte, nibble, number, register, variant_name, word},
+    disasm::{Syntax, format_instruction},
+    instruction::Instruction,
+};
+
+// Macros calling macros give up after this many expansions, it's most likely a recursive one.
+const MAX_EXPANSIONS: usize = 10_000;
+
+// Words that mean something on their own and can't be used as names.
+const KEYWORDS: [&str; 38] = [
+    ":=", "+=", "-=", "=-", "|=", "&=", "^=", ">>=", "<<=", "==", "!=", "<", ">", "<=", ">=",
+    "clear", "return", ";", "if", "then", "begin", "else", "end", "loop", "again", "while", "jump",
+    "jump0", "sprite", "save", "load", "bcd", "key", "-key", "i", "delay", "buzzer", "random",
+];
+
+pub struct Compiler {
+    variant: Variant,
+    origin: u16,
+}
+
+impl Compiler {
+    pub fn new() -> Compiler {
+        Compiler {
+            variant: Variant::default(),
+            origin: 0x200,
+        }
+    }
+
+    // Instructions outside the variant's set are errors. CHIP-8 by default.
+    pub fn with_variant(mut self, variant: Variant) -> Compiler {
+        self.variant = variant;
+        self
+    }
+
+    // Address the image gets loaded at, 0x200 by default.
+    pub fn with_origin(mut self, origin: u16) -> Compiler {
+        self.origin = origin;
+        self
+    }
+
+    pub fn compile(&self, source: &str) -> Result<Vec<u8>, AsmError> {
+        self.compile_named(source, "<source>")
+    }
+
+    pub fn compile_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, AsmError> {
+        let path = path.as_ref();
+        let name = path.display().to_string();
+        let source = fs::read_to_string(path).map_err(|e| AsmError {
+            file: name.clone(),
+            line: 0,
+            message: e.to_string(),
+        })?;
+        self.compile_named(&source, &name)
+    }
+
+    fn compile_named(&self, source: &str, file: &str) -> Result<Vec<u8>, AsmError> {
+        let mut program = Program {
+            variant: self.variant,
+            origin: self.origin as usize,
+            file,
+            tokens: tokenize(source),
+            line: 0,
+            image: Vec::new(),
+            here: self.origin as usize,
+            started: false,
+            main_jump: false,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+            expansions: 0,
+        };
+        program.compile()?;
+        Ok(program.image)
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Token {
+    text: String,
+    line: usize,
+}
+
+// Octo source is just words separated by whitespace.
+fn tokenize(source: &str) -> VecDeque<Token> {
+    let mut tokens = VecDeque::new();
+    for (index, line) in source.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or_default();
+        tokens.extend(code.split_whitespace().map(|text| Token {
+            text: text.to_string(),
+            line: index + 1,
+        }));
+    }
+    tokens
+}
+
+#[derive(Clone)]
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<String>,
+}
+
+// What to write once a label used before its definition is known.
+#[derive(Clone, Copy)]
+enum Patch {
+    // The low 12 bits of the opcode, for jumps, calls and i :=.
+    Nnn,
+    // The word after i := long.
+    Long,
+    // The byte of v0 := in :unpack, the nibble goes on top of the address' high 4 bits.
+    UnpackHigh(u8),
+    // The byte of v0 := in :unpack long.
+    LongHigh,
+    // The byte of v1 := in :unpack, the low 8 bits of the address.
+    Low,
+}
+
+struct Fixup {
+    address: usize,
+    label: String,
+    patch: Patch,
+    line: usize,
+}
+
+// Open control flow, innermost last.
+enum Block {
+    // loop, with the jumps out of it written by while.
+    Loop {
+        start: usize,
+        exits: Vec<usize>,
+        line: usize,
+    },
+    // if ... begin, with the jump to else or end.
+    If {
+        jump: usize,
+        line: usize,
+    },
+    // else, with the jump to end.
+    Else {
+        jump: usize,
+        line: usize,
+    },
+}
+
+// Right hand side of a condition.
+#[derive(Clone, Copy)]
+enum Operand {
+    Register(usize),
+    Byte(u8),
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Key,
+    NotKey,
+}
+
+impl Comparison {
+    fn negate(self) -> Comparison {
+        use Comparison::*;
+        match self {
+            Equal => NotEqual,
+            NotEqual => Equal,
+            Less => GreaterEqual,
+            GreaterEqual => Less,
+            Greater => LessEqual,
+            LessEqual => Greater,
+            Key => NotKey,
+            NotKey => Key,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Condition {
+    x: usize,
+    comparison: Comparison,
+    operand: Operand,
+}
+
+impl Condition {
+    fn negate(self) -> Condition {
+        Condition {
+            comparison: self.comparison.negate(),
+            ..self
+        }
+    }
+}
+
+struct Program<'a> {
+    variant: Variant,
+    origin: usize,
+    file: &'a str,
+    tokens: VecDeque<Token>,
+    // Line of the last token taken, where errors are reported
+    line: usize,
+    // Everything from origin on
+    image: Vec<u8>,
+    here: usize,
+    // Whether the jump to main was sorted out, and whether it takes up the first two bytes
+    started: bool,
+    main_jump: bool,
+    labels: HashMap<String, usize>,
+    constants: HashMap<String, i64>,
+    aliases: HashMap<String, usize>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+    expansions: usize,
+}
+
+impl Program<'_> {
+    fn compile(&mut self) -> Result<(), AsmError> {
+        while let Some(token) = self.tokens.pop_front() {
+            self.line = token.line;
+            self.statement(&token.text)?;
+        }
+
+        if let Some(block) = self.blocks.last() {
+            let (line, message) = match block {
+                Block::Loop { line, .. } => (*line, "loop without again"),
+                Block::If { line, .. } | Block::Else { line, .. } => (*line, "begin without end"),
+            };
+            self.line = line;
+            return Err(self.error(message.to_string()));
+        }
+
+        for fixup in std::mem::take(&mut self.fixups) {
+            self.line = fixup.line;
+            let Some(&target) = self.labels.get(&fixup.label) else {
+                return Err(self.error(if fixup.label == "main" {
+                    "the program has no ': main' label".to_string()
+                } else {
+                    format!("undefined name '{}'", fixup.label)
+                }));
+            };
+            self.patch(fixup.address, target, fixup.patch)?;
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: String) -> AsmError {
+        AsmError {
+            file: self.file.to_string(),
+            line: self.line,
+            message,
+        }
+    }
+
+    fn fail<T>(&self, message: String) -> Result<T, AsmError> {
+        Err(self.error(message))
+    }
+
+    fn next(&mut self) -> Result<String, AsmError> {
+        match self.tokens.pop_front() {
+            Some(token) => {
+                self.line = token.line;
+                Ok(token.text)
+            }
+            None => self.fail("unexpected end of file".to_string()),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.front().map(|token| token.text.as_str())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AsmError> {
+        let token = self.next()?;
+        if token != expected {
+            return self.fail(format!("expected '{}', found '{}'", expected, token));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self, token: &str) -> Result<(), AsmError> {
+        use Instruction::*;
+
+        if let Some(x) = self.register(token) {
+            return self.register_statement(x);
+        }
+        match token {
+            ":" => {
+                let name = self.next()?;
+                self.define_label(&name, 0)
+            }
+            ":const" => {
+                let name = self.next()?;
+                let token = self.next()?;
+                let value = self.value(&token)?;
+                self.define_constant(&name, value)
+            }
+            ":alias" => {
+                let name = self.next()?;
+                let token = self.next()?;
+                let Some(x) = self.register(&token) else {
+                    return self.fail(format!("expected a register, found '{}'", token));
+                };
+                self.check_name(&name)?;
+                self.aliases.insert(name, x);
+                Ok(())
+            }
+            ":calc" => {
+                let name = self.next()?;
+                self.expect("{")?;
+                let value = self.calc()?;
+                self.expect("}")?;
+                self.define_constant(&name, value)
+            }
+            ":macro" => self.define_macro(),
+            ":org" => {
+                let token = self.next()?;
+                let target = self.value(&token)?;
+                if target < self.origin as i64 || target > self.variant.memory_size() as i64 {
+                    return self.fail(format!("can't :org to {:#X}", target));
+                }
+                let target = target as usize;
+                // Nothing is at the origin yet, whatever comes next starts the program there
+                if !self.started && target == self.origin {
+                    return Ok(());
+                }
+                self.start();
+                if self.main_jump && target < self.origin + 2 {
+                    return self.fail(format!(
+                        "can't :org to {:#X}, the jump to main is there",
+                        target
+                    ));
+                }
+                self.here = target;
+                Ok(())
+            }
+            ":byte" => {
+                let token = self.next()?;
+                let value = self.value(&token)?;
+                let value = byte(value).map_err(|e| self.error(e))?;
+                self.emit_bytes(&[value])
+            }
+            ":call" => {
+                let token = self.next()?;
+                let nnn = self.address(&token, Patch::Nnn)?;
+                self.emit(Call(nnn))
+            }
+            ":unpack" => self.unpack(),
+            // Labels the second byte of the next instruction, for self-modifying code
+            ":next" => {
+                let name = self.next()?;
+                self.define_label(&name, 1)
+            }
+            // Octo's debugger annotations, nothing to do with the program itself
+            ":breakpoint" => self.next().map(drop),
+            ":monitor" => {
+                self.next()?;
+                self.next().map(drop)
+            }
+            "clear" => self.emit(Clear),
+            "return" | ";" => self.emit(Return),
+            "hires" => self.emit(HighRes),
+            "lores" => self.emit(LowRes),
+            "exit" => self.emit(Exit),
+            "scroll-left" => self.emit(ScrollLeft),
+            "scroll-right" => self.emit(ScrollRight),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(ScrollDown(n))
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(ScrollUp(n))
+            }
+            "plane" => {
+                let n = self.nibble()?;
+                self.emit(SelectPlanes(n))
+            }
+            "audio" => self.emit(LoadAudioPattern),
+            "bcd" => {
+                let x = self.next_register()?;
+                self.emit(BinaryToDecimal(x))
+            }
+            "save" | "load" => {
+                let x = self.next_register()?;
+                let save = token == "save";
+                let instruction = if self.peek() == Some("-") {
+                    self.next()?;
+                    let y = self.next_register()?;
+                    if save {
+                        StoreRange(x, y)
+                    } else {
+                        LoadRange(x, y)
+                    }
+                } else if save {
+                    StoreMemory(x)
+                } else {
+                    LoadMemory(x)
+                };
+                self.emit(instruction)
+            }
+            "saveflags" => {
+                let x = self.next_register()?;
+                self.emit(StoreFlags(x))
+            }
+            "loadflags" => {
+                let x = self.next_register()?;
+                self.emit(LoadFlags(x))
+            }
+            "sprite" => {
+                let x = self.next_register()?;
+                let y = self.next_register()?;
+                let n = self.nibble()?;
+                self.emit(Display(x, y, n))
+            }
+            "jump" => {
+                let token = self.next()?;
+                let nnn = self.address(&token, Patch::Nnn)?;
+                self.emit(Jump(nnn))
+            }
+            "jump0" => {
+                let token = self.next()?;
+                let nnn = self.address(&token, Patch::Nnn)?;
+                self.emit(JumpOffset(nnn))
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.next_register()?;
+                self.emit(match token {
+                    "delay" => SetDelayTimer(x),
+                    "buzzer" => SetSoundTimer(x),
+                    _ => SetPitch(x),
+                })
+            }
+            "i" => self.index_statement(),
+            "if" => {
+                let condition = self.condition()?;
+                match self.next()?.as_str() {
+                    // The next statement only runs if the condition holds
+                    "then" => self.skip_unless(condition),
+                    "begin" => {
+                        self.skip_unless(condition.negate())?;
+                        let jump = self.placeholder_jump()?;
+                        self.blocks.push(Block::If {
+                            jump,
+                            line: self.line,
+                        });
+                        Ok(())
+                    }
+                    other => self.fail(format!("expected then or begin, found '{}'", other)),
+                }
+            }
+            "else" => {
+                let Some(Block::If { jump, line }) = self.blocks.pop() else {
+                    return self.fail("else without if ... begin".to_string());
+                };
+                let end = self.placeholder_jump()?;
+                self.patch(jump, self.here, Patch::Nnn)?;
+                self.blocks.push(Block::Else { jump: end, line });
+                Ok(())
+            }
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump, .. } | Block::Else { jump, .. }) => {
+                    self.patch(jump, self.here, Patch::Nnn)
+                }
+                _ => self.fail("end without if ... begin".to_string()),
+            },
+            "loop" => {
+                self.start();
+                self.blocks.push(Block::Loop {
+                    start: self.here,
+                    exits: Vec::new(),
+                    line: self.line,
+                });
+                Ok(())
+            }
+            "while" => {
+                let condition = self.condition()?;
+                let Some(index) = self
+                    .blocks
+                    .iter()
+                    .rposition(|block| matches!(block, Block::Loop { .. }))
+                else {
+                    return self.fail("while outside of a loop".to_string());
+                };
+                // Leaves the loop when the condition doesn't hold
+                self.skip_unless(condition.negate())?;
+                let jump = self.placeholder_jump()?;
+                if let Block::Loop { exits, .. } = &mut self.blocks[index] {
+                    exits.push(jump);
+                }
+                Ok(())
+            }
+            "again" => {
+                let Some(Block::Loop { start, exits, .. }) = self.blocks.pop() else {
+                    return self.fail("again without loop".to_string());
+                };
+                let start = address(start as i64).map_err(|e| self.error(e))?;
+                self.emit(Jump(start))?;
+                for exit in exits {
+                    self.patch(exit, self.here, Patch::Nnn)?;
+                }
+                Ok(())
+            }
+            _ => {
+                if let Some(value) = number(token) {
+                    let value = byte(value).map_err(|e| self.error(e))?;
+                    return self.emit_bytes(&[value]);
+                }
+                if let Some(&value) = self.constants.get(token) {
+                    let value = byte(value).map_err(|e| self.error(e))?;
+                    return self.emit_bytes(&[value]);
+                }
+                if self.macros.contains_key(token) {
+                    return self.expand_macro(token);
+                }
+                if !is_name(token) {
+                    return self.fail(format!("unexpected '{}'", token));
+                }
+                // A label on its own is a call
+                let nnn = self.address(token, Patch::Nnn)?;
+                self.emit(Call(nnn))
+            }
+        }
+    }
+
+    fn register_statement(&mut self, x: usize) -> Result<(), AsmError> {
+        use Instruction::*;
+
+        let operator = self.next()?;
+        let token = self.next()?;
+        let y = self.register(&token);
+        let instruction = match (operator.as_str(), y) {
+            (":=", Some(y)) => SetRegister(x, y),
+            (":=", None) => match token.as_str() {
+                "random" => Random(x, self.byte()?),
+                "key" => GetKey(x),
+                "delay" => GetDelayTimer(x),
+                _ => Set(x, self.byte_value(&token)?),
+            },
+            ("+=", Some(y)) => AddRegister(x, y),
+            ("+=", None) => Add(x, self.byte_value(&token)?),
+            ("-=", Some(y)) => Subtract(x, y),
+            ("-=", None) => Add(x, self.byte_value(&token)?.wrapping_neg()),
+            ("=-", Some(y)) => SubtractInv(x, y),
+            ("|=", Some(y)) => OR(x, y),
+            ("&=", Some(y)) => AND(x, y),
+            ("^=", Some(y)) => XOR(x, y),
+            (">>=", Some(y)) => RShift(x, y),
+            ("<<=", Some(y)) => LShift(x, y),
+            ("=-" | "|=" | "&=" | "^=" | ">>=" | "<<=", None) => {
+                return self.fail(format!("{} needs a register, found '{}'", operator, token));
+            }
+            _ => return self.fail(format!("unknown operator '{}'", operator)),
+        };
+        self.emit(instruction)
+    }
+
+    fn index_statement(&mut self) -> Result<(), AsmError> {
+        use Instruction::*;
+
+        let operator = self.next()?;
+        let token = self.next()?;
+        match operator.as_str() {
+            ":=" => match token.as_str() {
+                "hex" => {
+                    let x = self.next_register()?;
+                    self.emit(GetFontCharacter(x))
+                }
+                "bighex" => {
+                    let x = self.next_register()?;
+                    self.emit(GetBigFontCharacter(x))
+                }
+                "long" => {
+                    let token = self.next()?;
+                    self.emit(LongIndex)?;
+                    // The address is the word after the opcode
+                    let nnnn = self.address(&token, Patch::Long)?;
+                    self.emit_bytes(&nnnn.to_be_bytes())
+                }
+                _ => {
+                    let nnn = self.address(&token, Patch::Nnn)?;
+                    self.emit(SetIndex(nnn))
+                }
+            },
+            "+=" => match self.register(&token) {
+                Some(x) => self.emit(AddToIndex(x)),
+                None => self.fail(format!("i += needs a register, found '{}'", token)),
+            },
+            _ => self.fail(format!("unknown operator '{}'", operator)),
+        }
+    }
+
+    // :unpack N label sets v0 and v1 to N and the label's 12 bit address, as in a Annn opcode.
+    // :unpack long label sets them to the label's full 16 bit address.
+    fn unpack(&mut self) -> Result<(), AsmError> {
+        let token = self.next()?;
+        let high = if token == "long" {
+            Patch::LongHigh
+        } else {
+            let value = self.value(&token)?;
+            Patch::UnpackHigh(nibble(value).map_err(|e| self.error(e))?)
+        };
+        let label = self.next()?;
+
+        let target = self.unresolved(&label)?;
+        self.start();
+        let (v0, v1) = (self.here, self.here + 2);
+        self.emit(Instruction::Set(0, 0))?;
+        self.emit(Instruction::Set(1, 0))?;
+        match target {
+            Some(target) => {
+                self.patch(v0, target, high)?;
+                self.patch(v1, target, Patch::Low)
+            }
+            None => {
+                self.fixup(v0, &label, high);
+                self.fixup(v1, &label, Patch::Low);
+                Ok(())
+            }
+        }
+    }
+
+    fn define_macro(&mut self) -> Result<(), AsmError> {
+        let name = self.next()?;
+        self.check_name(&name)?;
+        let mut parameters = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "{" {
+                break;
+            }
+            parameters.push(token);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 0;
+        loop {
+            let token = self.tokens.pop_front();
+            let Some(token) = token else {
+                return self.fail(format!("macro {} has no closing }}", name));
+            };
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" if depth == 0 => break,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            body.push(token.text);
+        }
+        self.macros.insert(name, Macro { parameters, body });
+        Ok(())
+    }
+
+    // The body goes back in front of the remaining tokens with the arguments in place of the parameters.
+    fn expand_macro(&mut self, name: &str) -> Result<(), AsmError> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return self.fail(format!("too many macro expansions, is {} recursive?", name));
+        }
+        let Macro { parameters, body } = self.macros[name].clone();
+        let mut arguments = HashMap::new();
+        for parameter in parameters {
+            let argument = self.next()?;
+            arguments.insert(parameter, argument);
+        }
+        // Errors inside the macro point at the line using it
+        let line = self.line;
+        for text in body.into_iter().rev() {
+            let text = arguments.get(&text).cloned().unwrap_or(text);
+            self.tokens.push_front(Token { text, line });
+        }
+        Ok(())
+    }
+
+    // Octo's calc has no precedence, operators apply right to left:
+    // `2 * 3 + 4` is 14, parentheses change the order.
+    fn calc(&mut self) -> Result<i64, AsmError> {
+        let term = self.calc_term()?;
+        let operator = self.next()?;
+        if operator == "}" || operator == ")" {
+            self.tokens.push_front(Token {
+                text: operator,
+                line: self.line,
+            });
+            return Ok(term);
+        }
+        let rest = self.calc()?;
+        let value = match operator.as_str() {
+            "+" => term.wrapping_add(rest),
+            "-" => term.wrapping_sub(rest),
+            "*" => term.wrapping_mul(rest),
+            "/" | "%" if rest == 0 => return self.fail("division by zero".to_string()),
+            "/" => term / rest,
+            "%" => term % rest,
+            "&" => term & rest,
+            "|" => term | rest,
+            "^" => term ^ rest,
+            "<<" => term.wrapping_shl(rest as u32),
+            ">>" => term.wrapping_shr(rest as u32),
+            "min" => term.min(rest),
+            "max" => term.max(rest),
+            _ => return self.fail(format!("unknown operator '{}' in :calc", operator)),
+        };
+        Ok(value)
+    }
+
+    fn calc_term(&mut self) -> Result<i64, AsmError> {
+        let token = self.next()?;
+        match token.as_str() {
+            "(" => {
+                let value = self.calc()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            "-" => Ok(self.calc_term()?.wrapping_neg()),
+            "~" => Ok(!self.calc_term()?),
+            "!" => Ok(i64::from(self.calc_term()? == 0)),
+            "HERE" => {
+                self.start();
+                Ok(self.here as i64)
+            }
+            _ => self.value(&token),
+        }
+    }
+
+    // A number, constant or label already defined, or a { calc } expression.
+    fn value(&mut self, token: &str) -> Result<i64, AsmError> {
+        if token == "{" {
+            let value = self.calc()?;
+            self.expect("}")?;
+            return Ok(value);
+        }
+        if let Some(value) = number(token) {
+            return Ok(value);
+        }
+        if let Some(&value) = self.constants.get(token) {
+            return Ok(value);
+        }
+        if let Some(&address) = self.labels.get(token) {
+            return Ok(address as i64);
+        }
+        self.fail(format!("unknown name or bad number '{}'", token))
+    }
+
+    fn byte_value(&mut self, token: &str) -> Result<u8, AsmError> {
+        let value = self.value(token)?;
+        byte(value).map_err(|e| self.error(e))
+    }
+
+    fn byte(&mut self) -> Result<u8, AsmError> {
+        let token = self.next()?;
+        self.byte_value(&token)
+    }
+
+    fn nibble(&mut self) -> Result<u8, AsmError> {
+        let token = self.next()?;
+        let value = self.value(&token)?;
+        nibble(value).map_err(|e| self.error(e))
+    }
+
+    // The address if it's known already, None for a label that isn't defined yet.
+    fn unresolved(&mut self, token: &str) -> Result<Option<usize>, AsmError> {
+        if let Some(&address) = self.labels.get(token) {
+            return Ok(Some(address));
+        }
+        if token == "{" || number(token).is_some() || self.constants.contains_key(token) {
+            return self.value(token).map(|value| Some(value as usize));
+        }
+        if !is_name(token) {
+            return self.fail(format!("expected an address, found '{}'", token));
+        }
+        Ok(None)
+    }
+
+    // Address operand of the instruction about to be emitted at here.
+    // Labels defined further down get patched in at the end.
+    fn address(&mut self, token: &str, patch: Patch) -> Result<u16, AsmError> {
+        match self.unresolved(token)? {
+            Some(target) => match patch {
+                Patch::Long => word(target as i64),
+                _ => address(target as i64),
+            }
+            .map_err(|e| self.error(e)),
+            None => {
+                self.start();
+                self.fixup(self.here, token, patch);
+                Ok(0)
+            }
+        }
+    }
+
+    fn fixup(&mut self, address: usize, label: &str, patch: Patch) {
+        self.fixups.push(Fixup {
+            address,
+            label: label.to_string(),
+            patch,
+            line: self.line,
+        });
+    }
+
+    fn patch(&mut self, at: usize, target: usize, patch: Patch) -> Result<(), AsmError> {
+        let offset = at - self.origin;
+        let value = match patch {
+            Patch::Nnn => address(target as i64),
+            _ => word(target as i64),
+        }
+        .map_err(|e| self.error(e))?;
+        match patch {
+            Patch::Nnn => {
+                self.image[offset] = (self.image[offset] & 0xF0) | (value >> 8) as u8;
+                self.image[offset + 1] = value as u8;
+            }
+            Patch::Long => self.image[offset..offset + 2].copy_from_slice(&value.to_be_bytes()),
+            Patch::UnpackHigh(n) => {
+                if value > 0xFFF {
+                    return self.fail(format!("address {:#X} doesn't fit in 12 bits", value));
+                }
+                self.image[offset + 1] = n << 4 | (value >> 8) as u8;
+            }
+            Patch::LongHigh => self.image[offset + 1] = (value >> 8) as u8,
+            Patch::Low => self.image[offset + 1] = value as u8,
+        }
+        Ok(())
+    }
+
+    fn placeholder_jump(&mut self) -> Result<usize, AsmError> {
+        self.start();
+        let at = self.here;
+        self.emit(Instruction::Jump(0))?;
+        Ok(at)
+    }
+
+    fn condition(&mut self) -> Result<Condition, AsmError> {
+        use Comparison::*;
+
+        let x = self.next_register()?;
+        let token = self.next()?;
+        let comparison = match token.as_str() {
+            "==" => Equal,
+            "!=" => NotEqual,
+            "<" => Less,
+            ">" => Greater,
+            "<=" => LessEqual,
+            ">=" => GreaterEqual,
+            "key" => Key,
+            "-key" => NotKey,
+            _ => return self.fail(format!("expected a comparison, found '{}'", token)),
+        };
+        let operand = if matches!(comparison, Key | NotKey) {
+            Operand::None
+        } else {
+            let token = self.next()?;
+            match self.register(&token) {
+                Some(y) => Operand::Register(y),
+                None => Operand::Byte(self.byte_value(&token)?),
+            }
+        };
+        Ok(Condition {
+            x,
+            comparison,
+            operand,
+        })
+    }
+
+    // Emits what skips the next instruction when the condition doesn't hold.
+    fn skip_unless(&mut self, condition: Condition) -> Result<(), AsmError> {
+        use Comparison::*;
+        use Instruction::*;
+
+        let Condition {
+            x,
+            comparison,
+            operand,
+        } = condition;
+        match (comparison, operand) {
+            (Equal, Operand::Register(y)) => self.emit(SNEQR(x, y)),
+            (Equal, Operand::Byte(nn)) => self.emit(SNEQ(x, nn)),
+            (NotEqual, Operand::Register(y)) => self.emit(SEQR(x, y)),
+            (NotEqual, Operand::Byte(nn)) => self.emit(SEQ(x, nn)),
+            (Key, _) => self.emit(SkipIfNotKey(x)),
+            (NotKey, _) => self.emit(SkipIfKey(x)),
+            // Ordering goes through a subtraction in vF, whose flag is 1 when there's no borrow
+            (Less | GreaterEqual, operand) => {
+                // vF := vx - operand, the flag says vx >= operand
+                match operand {
+                    Operand::Register(y) => {
+                        self.emit(SetRegister(0xF, x))?;
+                        self.emit(Subtract(0xF, y))?;
+                    }
+                    Operand::Byte(nn) => {
+                        self.emit(Set(0xF, nn))?;
+                        self.emit(SubtractInv(0xF, x))?;
+                    }
+                    Operand::None => unreachable!(),
+                }
+                let flag = u8::from(comparison == Less);
+                self.emit(SEQ(0xF, flag))
+            }
+            (Greater | LessEqual, operand) => {
+                // vF := operand - vx, the flag says operand >= vx
+                match operand {
+                    Operand::Register(y) => self.emit(SetRegister(0xF, y))?,
+                    Operand::Byte(nn) => self.emit(Set(0xF, nn))?,
+                    Operand::None => unreachable!(),
+                }
+                self.emit(Subtract(0xF, x))?;
+                let flag = u8::from(comparison == Greater);
+                self.emit(SEQ(0xF, flag))
+            }
+            (Equal | NotEqual, Operand::None) => unreachable!(),
+        }
+    }
+
+    fn register(&self, token: &str) -> Option<usize> {
+        register(token).or_else(|| self.aliases.get(token).copied())
+    }
+
+    fn next_register(&mut self) -> Result<usize, AsmError> {
+        let token = self.next()?;
+        match self.register(&token) {
+            Some(x) => Ok(x),
+            None => self.fail(format!("expected a register, found '{}'", token)),
+        }
+    }
+
+    fn check_name(&self, name: &str) -> Result<(), AsmError> {
+        if !is_name(name) || self.register(name).is_some() {
+            return self.fail(format!("'{}' can't be used as a name", name));
+        }
+        if self.labels.contains_key(name)
+            || self.constants.contains_key(name)
+            || self.aliases.contains_key(name)
+            || self.macros.contains_key(name)
+        {
+            return self.fail(format!("'{}' is already defined", name));
+        }
+        Ok(())
+    }
+
+    // The label goes offset bytes past the current address.
+    fn define_label(&mut self, name: &str, offset: usize) -> Result<(), AsmError> {
+        self.check_name(name)?;
+        // A program starting with main doesn't need a jump to it
+        if name == "main" && offset == 0 && self.here == self.origin {
+            self.started = true;
+        }
+        self.start();
+        self.labels.insert(name.to_string(), self.here + offset);
+        Ok(())
+    }
+
+    fn define_constant(&mut self, name: &str, value: i64) -> Result<(), AsmError> {
+        self.check_name(name)?;
+        self.constants.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    // Before anything lands at the origin, leaves room for the jump to main.
+    fn start(&mut self) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        self.main_jump = true;
+        self.image.resize(2, 0);
+        self.image[0] = 0x10;
+        self.fixup(self.origin, "main", Patch::Nnn);
+        if self.here == self.origin {
+            self.here += 2;
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> Result<(), AsmError> {
+        // Whatever decodes differently isn't part of this variant
+        let opcode = instruction.encode();
+        if Instruction::decode(opcode, self.variant) != Some(instruction) {
+            let [high, low] = opcode.to_be_bytes();
+            return self.fail(format!(
+                "{} isn't part of the {} instruction set",
+                format_instruction(instruction, &[high, low, 0, 0], Syntax::Octo),
+                variant_name(self.variant)
+            ));
+        }
+        self.emit_bytes(&opcode.to_be_bytes())
+    }
+
+    fn emit_bytes(&mut self, bytes: &[u8]) -> Result<(), AsmError> {
+        self.start();
+        let end = self.here + bytes.len();
+        if end > self.variant.memory_size() {
+            return self.fail(format!(
+                "program doesn't fit in memory, it reaches {:#X}",
+                end
+            ));
+        }
+        let offset = self.here - self.origin;
+        if self.image.len() < offset + bytes.len() {
+            self.image.resize(offset + bytes.len(), 0);
+        }
+        self.image[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self.here = end;
+        Ok(())
+    }
+}
+
+// Names can be almost anything in Octo, as long as they aren't numbers or keywords.
+fn is_name(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && !token.starts_with(':')
+        && number(token).is_none()
+        && !KEYWORDS.contains(&token)
+        && register(token).is_none()
+}
@@ -0,0 +1,37 @@
+use std::error::Error;
+
+use crate::{DirtyRegion, Framebuffer};
+
+mod png;
+mod terminal;
+mod window;
+
+pub use png::encode_png;
+pub use terminal::TerminalRenderer;
+pub use window::{Viewport, WindowRenderer};
+
+// Something that can put a frame on screen.
+// The core never draws by itself, frontends hand its framebuffer to a renderer instead.
+pub trait Renderer {
+    fn render(&mut self, frame: Framebuffer<'_>) -> Result<(), Box<dyn Error>>;
+
+    // Draws a frame when only region changed since the previous one.
+    // Renderers that can't redraw part of the screen just draw all of it.
+    fn render_region(
+        &mut self,
+        frame: Framebuffer<'_>,
+        _region: DirtyRegion,
+    ) -> Result<(), Box<dyn Error>> {
+        self.render(frame)
+    }
+}
+
+// RRGGBB, with or without a leading #, as taken by --fg and --bg.
+pub fn parse_color(text: &str) -> Option<(u8, u8, u8)> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    Some(((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
@@ -0,0 +1,112 @@
+use std::{
+    error::Error,
+    io::{Stdout, Write, stdout},
+};
+
+use crossterm::{
+    QueueableCommand,
+    cursor::MoveTo,
+    style::{Color, Print, ResetColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+
+use super::Renderer;
+use crate::{DirtyRegion, Framebuffer, PALETTE};
+
+// Draws the screen with Unicode blocks, two characters per pixel so it looks square.
+// Writes to stdout unless given another output.
+pub struct TerminalRenderer<W: Write = Stdout> {
+    out: W,
+    palette: [(u8, u8, u8); 4],
+    // Resolution of the last frame, the terminal gets cleared when it changes.
+    last_size: Option<(usize, usize)>,
+}
+
+impl TerminalRenderer {
+    pub fn new() -> TerminalRenderer {
+        TerminalRenderer::with_output(stdout())
+    }
+}
+
+impl<W: Write> TerminalRenderer<W> {
+    pub fn with_output(out: W) -> TerminalRenderer<W> {
+        TerminalRenderer {
+            out,
+            palette: PALETTE,
+            last_size: None,
+        }
+    }
+
+    // What has been written so far, for outputs that aren't a terminal.
+    pub fn output(&self) -> &W {
+        &self.out
+    }
+
+    // Colors for the XO-CHIP planes. Pixels lit on plane 1 only use the terminal's own color.
+    pub fn with_palette(mut self, palette: [(u8, u8, u8); 4]) -> TerminalRenderer<W> {
+        self.palette = palette;
+        self
+    }
+
+    // Every row is positioned explicitly, newlines don't return the carriage in raw mode.
+    fn draw_row(&mut self, y: usize, row: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.out.queue(MoveTo(0, y as u16))?;
+        for &pixel in row {
+            match pixel {
+                0 => self.out.queue(Print("  "))?,
+                1 => self.out.queue(Print("██"))?,
+                color => {
+                    let (r, g, b) = self.palette[color as usize & 0b11];
+                    self.out
+                        .queue(SetForegroundColor(Color::Rgb { r, g, b }))?
+                        .queue(Print("██"))?
+                        .queue(ResetColor)?
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn finish_frame(&mut self, frame: Framebuffer<'_>) -> Result<(), Box<dyn Error>> {
+        self.out.queue(MoveTo(0, frame.height() as u16))?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl Default for TerminalRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write> Renderer for TerminalRenderer<W> {
+    fn render(&mut self, frame: Framebuffer<'_>) -> Result<(), Box<dyn Error>> {
+        let size = (frame.width(), frame.height());
+        if self.last_size != Some(size) {
+            self.out.queue(Clear(ClearType::All))?;
+            self.last_size = Some(size);
+        }
+
+        for (y, row) in frame.rows().enumerate() {
+            self.draw_row(y, row)?;
+        }
+        self.finish_frame(frame)
+    }
+
+    // Only the dirty rows get printed again, terminals are slow at full redraws.
+    fn render_region(
+        &mut self,
+        frame: Framebuffer<'_>,
+        region: DirtyRegion,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.last_size != Some((frame.width(), frame.height())) {
+            return self.render(frame);
+        }
+
+        for y in region.rows().filter(|&y| y < frame.height()) {
+            self.draw_row(y, frame.row(y))?;
+        }
+        self.finish_frame(frame)
+    }
+}
@@ -0,0 +1,170 @@
+use std::{error::Error, sync::Arc};
+
+use pixels::{Pixels, SurfaceTexture, wgpu};
+use winit::window::Window;
+
+use super::Renderer;
+use crate::{Framebuffer, PALETTE};
+
+// Draws the screen in a winit window through pixels.
+// The pixel buffer is as big as the window: the CHIP-8 screen is scaled up by the largest whole factor that fits,
+// centered, and what's left around it is filled with the background color.
+pub struct WindowRenderer {
+    pixels: Pixels<'static>,
+    palette: [(u8, u8, u8); 4],
+    // Size of the window, and of the pixel buffer
+    window_size: (usize, usize),
+    // Dims the screen and draws a pause sign over it
+    paused: bool,
+}
+
+// Where a screen of some resolution goes in a window: scale is the largest whole factor that fits,
+// at least 1, and x, y is the top left corner that centers it.
+// A window smaller than the screen cuts off its right and bottom edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub scale: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    pub fn fit(resolution: (usize, usize), window: (usize, usize)) -> Viewport {
+        let (width, height) = (resolution.0.max(1), resolution.1.max(1));
+        let scale = (window.0 / width).min(window.1 / height).max(1);
+        let (width, height) = (width * scale, height * scale);
+        Viewport {
+            scale,
+            x: window.0.saturating_sub(width) / 2,
+            y: window.1.saturating_sub(height) / 2,
+            width,
+            height,
+        }
+    }
+}
+
+impl WindowRenderer {
+    pub fn new(window: Arc<Window>) -> Result<WindowRenderer, pixels::Error> {
+        let size = window.inner_size();
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        let surface = SurfaceTexture::new(width, height, window);
+        let mut pixels = Pixels::new(width, height, surface)?;
+        pixels.clear_color(clear_color(PALETTE[0]));
+
+        Ok(WindowRenderer {
+            pixels,
+            palette: PALETTE,
+            window_size: (width as usize, height as usize),
+            paused: false,
+        })
+    }
+
+    // Colors for background, plane 1, plane 2 and both planes.
+    pub fn with_palette(mut self, palette: [(u8, u8, u8); 4]) -> WindowRenderer {
+        self.palette = palette;
+        self.pixels.clear_color(clear_color(palette[0]));
+        self
+    }
+
+    // Takes effect on the next render.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    // Must be called when the window is resized.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), Box<dyn Error>> {
+        if width > 0 && height > 0 {
+            self.pixels.resize_surface(width, height)?;
+            self.pixels.resize_buffer(width, height)?;
+            self.window_size = (width as usize, height as usize);
+        }
+        Ok(())
+    }
+
+    // What a screen pixel is drawn as. While paused it's dimmed, unless the pause sign covers it.
+    fn color(&self, pixel: u8, pause_sign: bool) -> [u8; 4] {
+        let (r, g, b) = self.palette[pixel as usize & 0b11];
+        if !self.paused {
+            return [r, g, b, 0xFF];
+        }
+        if pause_sign {
+            let (r, g, b) = self.palette[1];
+            return [r, g, b, 0xFF];
+        }
+        // A third of the way from the background to the color
+        let background = self.palette[0];
+        let dim = |color: u8, background: u8| {
+            (background as i16 + (color as i16 - background as i16) / 3) as u8
+        };
+        [
+            dim(r, background.0),
+            dim(g, background.1),
+            dim(b, background.2),
+            0xFF,
+        ]
+    }
+}
+
+impl Renderer for WindowRenderer {
+    fn render(&mut self, frame: Framebuffer<'_>) -> Result<(), Box<dyn Error>> {
+        let (width, height) = (frame.width(), frame.height());
+        let (window_width, window_height) = self.window_size;
+        let viewport = Viewport::fit((width, height), self.window_size);
+        let scale = viewport.scale;
+
+        let colors: Vec<[u8; 4]> = frame
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| row.iter().enumerate().map(move |(x, &pixel)| (x, y, pixel)))
+            .map(|(x, y, pixel)| self.color(pixel, in_pause_sign(width, height, x, y)))
+            .collect();
+
+        let (r, g, b) = self.palette[0];
+        let target = self.pixels.frame_mut();
+        for pixel in target.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+        for (y, row) in colors.chunks_exact(width).enumerate() {
+            let top = viewport.y + y * scale;
+            for line in top..(top + scale).min(window_height) {
+                for (x, color) in row.iter().enumerate() {
+                    let left = viewport.x + x * scale;
+                    let right = (left + scale).min(window_width);
+                    if left >= right {
+                        break;
+                    }
+                    let start = (line * window_width + left) * 4;
+                    let end = (line * window_width + right) * 4;
+                    for pixel in target[start..end].chunks_exact_mut(4) {
+                        pixel.copy_from_slice(color);
+                    }
+                }
+            }
+        }
+
+        self.pixels.render()?;
+        Ok(())
+    }
+}
+
+// Two bars in the middle of the screen, 8 pixels tall in low resolution and twice that in high.
+fn in_pause_sign(width: usize, height: usize, x: usize, y: usize) -> bool {
+    let unit = (width / 64).max(1);
+    let (left, top) = (
+        (width / 2).saturating_sub(3 * unit),
+        (height / 2).saturating_sub(4 * unit),
+    );
+    (top..top + 8 * unit).contains(&y)
+        && ((left..left + 2 * unit).contains(&x) || (left + 4 * unit..left + 6 * unit).contains(&x))
+}
+
+fn clear_color((r, g, b): (u8, u8, u8)) -> wgpu::Color {
+    wgpu::Color {
+        r: r as f64 / 255.0,
+        g: g as f64 / 255.0,
+        b: b as f64 / 255.0,
+        a: 1.0,
+    }
+}
@@ -0,0 +1,172 @@
+// Rewinding: a bounded history of save states, taken every few frames, that the machine can step back through.
+//
+// Only the newest snapshot is kept whole. Every older one is stored as the changes that turn the snapshot after it
+// back into it, and since little of the memory and the display changes in a few frames those are a few hundred bytes.
+// Stepping back undoes the newest changes and forgetting the oldest snapshot drops the oldest ones, so both are cheap.
+//
+// Changes are runs of the target snapshot: u32 length of the target, then for every run
+// u32 count of unchanged bytes to copy, u32 count of new bytes, the new bytes.
+
+use std::collections::VecDeque;
+
+use crate::{
+    Chip8, Chip8Error,
+    state::{Reader, Writer},
+};
+
+// A snapshot every 10 frames, a minute's worth of them.
+pub const DEFAULT_REWIND_INTERVAL: u32 = 10;
+pub const DEFAULT_REWIND_CAPACITY: usize = 360;
+
+// Unchanged stretches shorter than a run header are cheaper to store as new bytes.
+const RUN_HEADER: usize = 8;
+
+pub struct Rewind {
+    interval: u32,
+    capacity: usize,
+    // Frames since the last snapshot
+    frames: u32,
+    newest: Option<Vec<u8>>,
+    // Oldest first, the last one turns newest into the snapshot before it
+    changes: VecDeque<Vec<u8>>,
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rewind {
+    pub fn new() -> Rewind {
+        Rewind {
+            interval: DEFAULT_REWIND_INTERVAL,
+            capacity: DEFAULT_REWIND_CAPACITY,
+            frames: 0,
+            newest: None,
+            changes: VecDeque::new(),
+        }
+    }
+
+    // Frames between snapshots. Holding rewind steps back this many frames at a time.
+    pub fn with_interval(mut self, frames: u32) -> Rewind {
+        self.interval = frames.max(1);
+        self
+    }
+
+    // Snapshots kept, the oldest are forgotten past it.
+    pub fn with_capacity(mut self, snapshots: usize) -> Rewind {
+        self.capacity = snapshots.max(1);
+        self
+    }
+
+    // Call after every frame, it takes a snapshot when one is due. The first call always takes one.
+    pub fn record(&mut self, chip8: &Chip8) {
+        self.frames += 1;
+        if self.newest.is_some() && self.frames < self.interval {
+            return;
+        }
+        self.frames = 0;
+        self.push(chip8.save_state());
+    }
+
+    // Adds a snapshot right away, whatever the interval.
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(newest) = self.newest.take() {
+            self.changes.push_back(diff(&state, &newest));
+        }
+        self.newest = Some(state);
+        while self.changes.len() >= self.capacity {
+            self.changes.pop_front();
+        }
+    }
+
+    // Loads the newest snapshot and forgets it, so the next call goes further back.
+    // The oldest one stays, holding rewind at the start of the history keeps loading it. False when there's nothing to load.
+    pub fn step_back(&mut self, chip8: &mut Chip8) -> Result<bool, Chip8Error> {
+        let Some(newest) = &self.newest else {
+            return Ok(false);
+        };
+        chip8.load_state(newest)?;
+        if let Some(changes) = self.changes.pop_back() {
+            self.newest = Some(apply(newest, &changes)?);
+        }
+        // Recording picks up a whole interval after the loaded snapshot
+        self.frames = 0;
+        Ok(true)
+    }
+
+    // Snapshots in the history.
+    pub fn len(&self) -> usize {
+        self.newest.as_ref().map_or(0, |_| self.changes.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.changes.clear();
+        self.frames = 0;
+    }
+
+    // Bytes the history takes up.
+    pub fn size(&self) -> usize {
+        self.newest.as_ref().map_or(0, Vec::len) + self.changes.iter().map(Vec::len).sum::<usize>()
+    }
+}
+
+// The changes that turn from into to.
+fn diff(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let same = |i: usize| from.get(i) == Some(&to[i]);
+    let mut changes = Writer(Vec::new());
+    changes.u32(to.len() as u32);
+
+    // End of the last run
+    let mut copied = 0;
+    let mut i = 0;
+    while i < to.len() {
+        if same(i) {
+            i += 1;
+            continue;
+        }
+        // The run goes on until enough unchanged bytes in a row
+        let start = i;
+        let mut end = i + 1;
+        let mut j = end;
+        while j < to.len() && j - end < RUN_HEADER {
+            if !same(j) {
+                end = j + 1;
+            }
+            j += 1;
+        }
+        changes.u32((start - copied) as u32);
+        changes.u32((end - start) as u32);
+        changes.bytes(&to[start..end]);
+        copied = end;
+        i = end;
+    }
+    changes.0
+}
+
+fn apply(from: &[u8], changes: &[u8]) -> Result<Vec<u8>, Chip8Error> {
+    const BROKEN: Chip8Error = Chip8Error::InvalidSaveState("broken rewind history");
+    let mut reader = Reader(changes);
+    let len = reader.u32()? as usize;
+    let mut to = Vec::with_capacity(len);
+    // Runs replace bytes one for one, to and from line up
+    while !reader.0.is_empty() {
+        let unchanged = reader.u32()? as usize;
+        let new = reader.u32()? as usize;
+        let position = to.len();
+        to.extend_from_slice(from.get(position..position + unchanged).ok_or(BROKEN)?);
+        to.extend_from_slice(reader.bytes(new)?);
+    }
+    // Whatever follows the last run is unchanged
+    let position = to.len();
+    if position < len {
+        to.extend_from_slice(from.get(position..len).ok_or(BROKEN)?);
+    }
+    Ok(to)
+}
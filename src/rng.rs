@@ -0,0 +1,42 @@
+// Random number generator owned by the machine, so that save states can capture it.
+// xorshift64*: tiny, fast and plenty random for CXNN.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn from_entropy() -> Rng {
+        Rng::from_state(rand::random())
+    }
+
+    // Seeds are run through splitmix64 first, so nearby seeds like 1 and 2 still give unrelated sequences.
+    pub(crate) fn from_seed(seed: u64) -> Rng {
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Rng::from_state(z ^ (z >> 31))
+    }
+
+    // xorshift gets stuck at 0, so that's the one state that can't be used.
+    pub(crate) fn from_state(state: u64) -> Rng {
+        Rng {
+            state: if state == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                state
+            },
+        }
+    }
+
+    pub(crate) fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub(crate) fn next_u8(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+}
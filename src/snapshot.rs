@@ -0,0 +1,236 @@
+// Screens as ASCII art, for tests and snapshots. One line per row, one character per pixel:
+//
+//     ..##..      . off
+//     .#22#.      # lit, on plane 1
+//     ..33..      2 and 3 are XO-CHIP colors: plane 2 only, and both planes
+//
+// assert_screen_eq! compares two screens and prints where they differ.
+
+use std::fmt;
+
+use crate::{Chip8, Framebuffer};
+
+const PIXEL_CHARS: [char; 4] = ['.', '#', '2', '3'];
+
+// An owned copy of the screen, the visible area only.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Screen {
+    // Parses ASCII art. Leading and trailing whitespace is ignored on every line, and so are blank lines,
+    // so indented string literals work. Every row must be as wide as the first.
+    pub fn from_ascii(text: &str) -> Result<Screen, String> {
+        let rows: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        let mut pixels = Vec::with_capacity(width * rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!(
+                    "row {} is {} pixels wide, expected {}",
+                    y,
+                    row.chars().count(),
+                    width
+                ));
+            }
+            for (x, c) in row.chars().enumerate() {
+                let color = PIXEL_CHARS
+                    .iter()
+                    .position(|&pixel| pixel == c)
+                    .ok_or_else(|| format!("unexpected '{}' at x {} y {}", c, x, y))?;
+                pixels.push(color as u8);
+            }
+        }
+        Ok(Screen {
+            width,
+            height: rows.len(),
+            pixels,
+        })
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            text.extend(row.iter().map(|&color| PIXEL_CHARS[color as usize & 3]));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    // The part of the screen at x, y, cut to fit. Handy to check a sprite without drawing out the whole screen.
+    pub fn region(&self, x: usize, y: usize, width: usize, height: usize) -> Screen {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in y..y + height {
+            let start = row * self.width + x;
+            pixels.extend_from_slice(&self.pixels[start..start + width]);
+        }
+        Screen {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // None when the screens are the same. Otherwise how many pixels differ and a view of expected
+    // with every mismatch marked by a !, followed by what the screen actually holds.
+    pub fn diff(&self, expected: &Screen) -> Option<String> {
+        if (self.width, self.height) != (expected.width, expected.height) {
+            return Some(format!(
+                "the screen is {}x{}, expected {}x{}\nactual:\n{}",
+                self.width,
+                self.height,
+                expected.width,
+                expected.height,
+                self.to_ascii()
+            ));
+        }
+
+        let mismatches = self
+            .pixels
+            .iter()
+            .zip(&expected.pixels)
+            .filter(|(a, b)| a != b)
+            .count();
+        if mismatches == 0 {
+            return None;
+        }
+        let first = self
+            .pixels
+            .iter()
+            .zip(&expected.pixels)
+            .position(|(a, b)| a != b)
+            .unwrap_or_default();
+
+        let mut view = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (actual, wanted) = (self.pixel(x, y), expected.pixel(x, y));
+                view.push(if actual == wanted {
+                    PIXEL_CHARS[wanted as usize & 3]
+                } else {
+                    '!'
+                });
+            }
+            view.push('\n');
+        }
+        Some(format!(
+            "{} pixels differ, the first one at x {} y {}\nexpected, ! where the screen differs:\n{}actual:\n{}",
+            mismatches,
+            first % self.width,
+            first / self.width,
+            view,
+            self.to_ascii()
+        ))
+    }
+}
+
+impl fmt::Debug for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}\n{}", self.width, self.height, self.to_ascii())
+    }
+}
+
+impl Framebuffer<'_> {
+    pub fn to_screen(&self) -> Screen {
+        Screen {
+            width: self.width(),
+            height: self.height(),
+            pixels: self.rows().flatten().copied().collect(),
+        }
+    }
+
+    pub fn to_ascii(&self) -> String {
+        self.to_screen().to_ascii()
+    }
+}
+
+// Anything assert_screen_eq! can compare: screens, framebuffers, machines and ASCII art.
+pub trait ToScreen {
+    fn to_screen(&self) -> Screen;
+}
+
+impl ToScreen for Screen {
+    fn to_screen(&self) -> Screen {
+        self.clone()
+    }
+}
+
+impl ToScreen for Framebuffer<'_> {
+    fn to_screen(&self) -> Screen {
+        Framebuffer::to_screen(self)
+    }
+}
+
+impl ToScreen for Chip8 {
+    fn to_screen(&self) -> Screen {
+        self.framebuffer().to_screen()
+    }
+}
+
+// Panics if the art doesn't parse, it's meant for expected values written in tests.
+impl ToScreen for str {
+    fn to_screen(&self) -> Screen {
+        Screen::from_ascii(self).unwrap_or_else(|e| panic!("invalid screen: {}", e))
+    }
+}
+
+impl ToScreen for String {
+    fn to_screen(&self) -> Screen {
+        self.as_str().to_screen()
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_screen_eq(actual: Screen, expected: Screen, message: Option<fmt::Arguments<'_>>) {
+    if let Some(diff) = actual.diff(&expected) {
+        match message {
+            Some(message) => panic!("screens don't match: {}\n{}", message, diff),
+            None => panic!("screens don't match\n{}", diff),
+        }
+    }
+}
+
+// Compares the first screen with the second and panics with a diff when they differ.
+// Both sides can be a Screen, a Framebuffer, a Chip8 or ASCII art.
+//
+//     assert_screen_eq!(chip8.framebuffer().to_screen().region(0, 0, 4, 2), "
+//         #..#
+//         .##.
+//     ");
+#[macro_export]
+macro_rules! assert_screen_eq {
+    ($actual:expr, $expected:expr $(,)?) => {{
+        use $crate::snapshot::ToScreen as _;
+        $crate::snapshot::assert_screen_eq(($actual).to_screen(), ($expected).to_screen(), None)
+    }};
+    ($actual:expr, $expected:expr, $($message:tt)+) => {{
+        use $crate::snapshot::ToScreen as _;
+        $crate::snapshot::assert_screen_eq(
+            ($actual).to_screen(),
+            ($expected).to_screen(),
+            Some(format_args!($($message)+)),
+        )
+    }};
+}
@@ -0,0 +1,247 @@
+// Save states: the whole machine as bytes, and back.
+//
+// Layout, all integers little endian:
+//   "R8ST" magic, u16 version, u32 payload length, payload, u32 CRC-32 of the payload.
+// The payload is the fields below in the order save_state writes them.
+// Memory is stored up to the variant's memory size, so a CHIP-8 state is about 12K.
+
+use crate::{
+    Chip8, Chip8Error, DirtyRegion, HIRES_DISPLAY_SIZE_X, Quirks, STACK_SIZE, Timing, Variant,
+    rng::Rng,
+};
+
+const MAGIC: &[u8; 4] = b"R8ST";
+// Version 2 added the key held during FX0A, version 3 the timing mode and the cycles spent in the frame.
+// Older states still load.
+const VERSION: u16 = 3;
+// Magic, version and payload length
+const HEADER_SIZE: usize = 10;
+const CHECKSUM_SIZE: usize = 4;
+
+impl Chip8 {
+    // Captures everything needed to continue the run later, including quirks and RNG state.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut payload = Writer(Vec::with_capacity(self.memory_size() + 8 * 1024 + 128));
+
+        payload.u8(self.variant.id());
+        payload.u8(self.quirks.to_bits());
+        payload.u16(self.entry_point);
+
+        payload.bytes(&self.memory[..self.memory_size()]);
+        payload.u16(self.program_counter);
+        payload.u16(self.i);
+        for &address in &self.stack {
+            payload.u16(address);
+        }
+        payload.u8(self.sp as u8);
+        payload.u8(self.delay);
+        payload.u8(self.sound);
+        payload.bytes(&self.v);
+
+        payload.u8(u8::from(self.hires));
+        payload.u8(self.planes);
+        for row in &self.display {
+            payload.bytes(row);
+        }
+        payload.u8(u8::from(self.halted));
+        payload.bytes(&self.rpl);
+        payload.bytes(&self.audio_pattern);
+        payload.u8(self.pitch);
+
+        let keys = self
+            .keyboard
+            .iter()
+            .enumerate()
+            .fold(0u16, |keys, (key, &down)| keys | (u16::from(down) << key));
+        payload.u16(keys);
+        payload.u8(self.waiting_for_key.map_or(0xFF, |x| x as u8));
+        payload.u8(self.held_key.unwrap_or(0xFF));
+        payload.u8(u8::from(self.waiting_for_vblank));
+        payload.u64(self.rng.state());
+        payload.u8(self.timing.id());
+        payload.u16(self.frame_cycles.min(u16::MAX as u32) as u16);
+
+        let payload = payload.0;
+        let mut state = Writer(Vec::with_capacity(
+            HEADER_SIZE + payload.len() + CHECKSUM_SIZE,
+        ));
+        state.bytes(MAGIC);
+        state.u16(VERSION);
+        state.u32(payload.len() as u32);
+        state.bytes(&payload);
+        state.u32(crc32(&payload));
+        state.0
+    }
+
+    // Restores a state made by save_state. The machine is only touched if the whole state is valid.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), Chip8Error> {
+        if state.len() < HEADER_SIZE + CHECKSUM_SIZE || &state[0..4] != MAGIC {
+            return Err(Chip8Error::InvalidSaveState("not a save state"));
+        }
+        let version = u16::from_le_bytes([state[4], state[5]]);
+        if version == 0 || version > VERSION {
+            return Err(Chip8Error::InvalidSaveState("unsupported version"));
+        }
+        let length = u32::from_le_bytes([state[6], state[7], state[8], state[9]]) as usize;
+        if state.len() != HEADER_SIZE + length + CHECKSUM_SIZE {
+            return Err(Chip8Error::InvalidSaveState("truncated"));
+        }
+        let payload = &state[HEADER_SIZE..HEADER_SIZE + length];
+        let checksum = &state[HEADER_SIZE + length..];
+        if crc32(payload).to_le_bytes() != checksum {
+            return Err(Chip8Error::InvalidSaveState("checksum mismatch"));
+        }
+
+        // Everything is read into a copy first, so a bad state can't leave a half loaded machine.
+        let mut reader = Reader(payload);
+        let mut next = Chip8::new();
+        // The seed is a setting of this machine, the generator state comes from the save
+        next.seed = self.seed;
+
+        next.variant = Variant::from_id(reader.u8()?)
+            .ok_or(Chip8Error::InvalidSaveState("unknown variant"))?;
+        next.quirks = Quirks::from_bits(reader.u8()?);
+        next.entry_point = reader.u16()?;
+
+        let memory_size = next.memory_size();
+        next.memory[..memory_size].copy_from_slice(reader.bytes(memory_size)?);
+        next.program_counter = reader.u16()?;
+        next.i = reader.u16()?;
+        for address in next.stack.iter_mut() {
+            *address = reader.u16()?;
+        }
+        next.sp = reader.u8()? as usize;
+        if next.sp > STACK_SIZE {
+            return Err(Chip8Error::InvalidSaveState("stack pointer out of range"));
+        }
+        next.delay = reader.u8()?;
+        next.sound = reader.u8()?;
+        next.v.copy_from_slice(reader.bytes(16)?);
+
+        next.hires = reader.u8()? != 0;
+        next.planes = reader.u8()? & 0b11;
+        for row in next.display.iter_mut() {
+            row.copy_from_slice(reader.bytes(HIRES_DISPLAY_SIZE_X)?);
+        }
+        next.halted = reader.u8()? != 0;
+        next.rpl.copy_from_slice(reader.bytes(16)?);
+        next.audio_pattern.copy_from_slice(reader.bytes(16)?);
+        next.pitch = reader.u8()?;
+
+        let keys = reader.u16()?;
+        for (key, down) in next.keyboard.iter_mut().enumerate() {
+            *down = keys & (1 << key) != 0;
+        }
+        next.waiting_for_key = match reader.u8()? {
+            0xFF => None,
+            x if x < 16 => Some(x as usize),
+            _ => return Err(Chip8Error::InvalidSaveState("bad key wait register")),
+        };
+        if version >= 2 {
+            next.held_key = match reader.u8()? {
+                0xFF => None,
+                key if key < 16 => Some(key),
+                _ => return Err(Chip8Error::InvalidSaveState("bad held key")),
+            };
+        }
+        next.waiting_for_vblank = reader.u8()? != 0;
+        next.rng = Rng::from_state(reader.u64()?);
+        if version >= 3 {
+            next.timing = Timing::from_id(reader.u8()?)
+                .ok_or(Chip8Error::InvalidSaveState("unknown timing"))?;
+            next.frame_cycles = reader.u16()?.into();
+        } else {
+            next.timing = self.timing;
+        }
+
+        if !reader.0.is_empty() {
+            return Err(Chip8Error::InvalidSaveState("trailing data"));
+        }
+
+        next.update_display = true;
+        next.dirty = Some(DirtyRegion::full(
+            next.display_width(),
+            next.display_height(),
+        ));
+        // Frame length is up to whoever runs the machine, like the seed
+        next.cycles_per_frame = self.cycles_per_frame;
+        // The tracer isn't saved either, it keeps logging across loads
+        next.tracer = self.tracer.take();
+        // Same for the audio sink, the next tick tells it if the beeper changed
+        next.audio = self.audio.take();
+        next.beeper_on = self.beeper_on;
+        *self = next;
+        Ok(())
+    }
+}
+
+// Also used for the rewind history.
+pub(crate) struct Writer(pub(crate) Vec<u8>);
+
+impl Writer {
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], Chip8Error> {
+        if self.0.len() < len {
+            return Err(Chip8Error::InvalidSaveState("truncated"));
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Chip8Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Chip8Error> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, Chip8Error> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, Chip8Error> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+}
+
+// Plain bitwise CRC-32 (IEEE), save states are small enough not to need a table.
+// PNG screenshots use it for their chunks too.
+pub(crate) fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
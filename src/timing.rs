@@ -0,0 +1,136 @@
+// How long instructions take. By default they all cost the same and the frontend picks how many run per frame.
+// With COSMAC VIP timing every instruction costs the machine cycles the original interpreter spent on it,
+// and a frame ends when its cycles run out, so programs run at the speed they had on the real machine.
+//
+// The 1802 in the VIP runs at 1.7609 MHz with 8 clocks per machine cycle: 3668 cycles per 60 Hz frame.
+// Video DMA takes 1024 of them (128 lines of 8 bytes) and the interrupt routine about 46 more.
+// The costs below are approximate, worked out from the interpreter's routines: fetching and dispatching
+// an instruction is part of every cost, and the work that depends on operands is counted per row, digit or register.
+
+use crate::{Chip8, instruction::Instruction};
+
+// Machine cycles the interpreter gets in every frame.
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668 - 1024 - 46;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timing {
+    // Every instruction takes one step.
+    #[default]
+    Fixed,
+    // Instructions take as long as on the COSMAC VIP and sprites wait for vblank.
+    CosmacVip,
+}
+
+impl Timing {
+    pub const NAMES: [&'static str; 2] = ["fixed", "vip"];
+
+    pub fn from_name(name: &str) -> Option<Timing> {
+        match name.to_ascii_lowercase().as_str() {
+            "fixed" => Some(Timing::Fixed),
+            "vip" | "cosmac" => Some(Timing::CosmacVip),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Timing::Fixed => 0,
+            Timing::CosmacVip => 1,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Timing> {
+        match id {
+            0 => Some(Timing::Fixed),
+            1 => Some(Timing::CosmacVip),
+            _ => None,
+        }
+    }
+}
+
+impl Chip8 {
+    // Machine cycles instruction takes on the VIP, given the state it's about to run in.
+    pub(crate) fn vip_cycles(&self, instruction: Instruction) -> u32 {
+        use Instruction::*;
+        let skip = |taken: bool| if taken { 2 } else { 0 };
+        match instruction {
+            // Clears the 256 bytes of display memory, 4 at a time
+            Clear => 24 + 64 * 12,
+            Return | Jump(_) | Call(_) | JumpOffset(_) => 23,
+            SEQ(x, nn) => 12 + skip(self.v[x] == nn),
+            SNEQ(x, nn) => 12 + skip(self.v[x] != nn),
+            SEQR(x, y) => 16 + skip(self.v[x] == self.v[y]),
+            SNEQR(x, y) => 16 + skip(self.v[x] != self.v[y]),
+            Set(..) => 6,
+            Add(..) => 10,
+            // The interpreter builds and runs a tiny 1802 routine for every arithmetic opcode
+            SetRegister(..) | OR(..) | AND(..) | XOR(..) | AddRegister(..) | Subtract(..)
+            | SubtractInv(..) | LShift(..) | RShift(..) => 44,
+            SetIndex(_) => 12,
+            Random(..) => 36,
+            Display(x, _, n) => {
+                // Every row is shifted into place bit by bit, and rows that aren't byte aligned touch two bytes
+                let offset = u32::from(self.v[x] % 8);
+                let row = if offset == 0 { 27 } else { 43 + 4 * offset };
+                26 + u32::from(n) * row
+            }
+            SkipIfKey(x) => 16 + skip(self.keyboard[self.v[x] as usize & 0xF]),
+            SkipIfNotKey(x) => 16 + skip(!self.keyboard[self.v[x] as usize & 0xF]),
+            GetDelayTimer(_) | SetDelayTimer(_) | SetSoundTimer(_) => 10,
+            AddToIndex(_) => 19,
+            // Each pass polls the keypad once
+            GetKey(_) => 20,
+            GetFontCharacter(_) => 20,
+            // Counted out one subtraction per unit of every digit
+            BinaryToDecimal(x) => {
+                let value = self.v[x];
+                let digits = u32::from(value / 100 + value / 10 % 10 + value % 10);
+                60 + 14 * digits
+            }
+            StoreMemory(x) | LoadMemory(x) => 14 + 14 * (x as u32 + 1),
+            // Not VIP instructions, they get the cost of a typical one
+            _ => 23,
+        }
+    }
+
+    // What instruction is about to cost: its VIP cycles with VIP timing, nothing otherwise.
+    pub(crate) fn instruction_cycles(&self, instruction: Instruction) -> u32 {
+        match self.timing {
+            Timing::Fixed => 0,
+            Timing::CosmacVip => self.vip_cycles(instruction),
+        }
+    }
+
+    // The frame ends once its cycles are used up.
+    pub(crate) fn spend_cycles(&mut self, cycles: u32) {
+        if cycles == 0 {
+            return;
+        }
+        self.frame_cycles += cycles;
+        if self.frame_cycles >= VIP_CYCLES_PER_FRAME {
+            self.waiting_for_vblank = true;
+        }
+    }
+
+    // At vblank: cycles past the end of the last frame come out of the new one.
+    pub(crate) fn start_frame_cycles(&mut self) {
+        self.frame_cycles = self.frame_cycles.saturating_sub(VIP_CYCLES_PER_FRAME);
+        if self.frame_cycles >= VIP_CYCLES_PER_FRAME {
+            self.waiting_for_vblank = true;
+        }
+    }
+
+    pub fn with_timing(mut self, timing: Timing) -> Chip8 {
+        self.timing = timing;
+        self
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    // Machine cycles used so far in this frame, with VIP timing.
+    pub fn frame_cycles(&self) -> u32 {
+        self.frame_cycles
+    }
+}
@@ -0,0 +1,132 @@
+// Execution trace: one line per instruction, with what it changed.
+//
+//        1234  20A  7005  v0 += 0x05                V0 03->08
+//        1235  20C  A300  i := 0x300                I 210->300
+//
+// The cycle counts every instruction the tracer saw, including the ones filtered out.
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    path::Path,
+};
+
+use crate::{
+    Chip8, Chip8Error,
+    disasm::{Syntax, format_instruction},
+};
+
+pub struct Tracer {
+    out: Box<dyn Write + Send>,
+    // Only instructions at these addresses are logged
+    range: Option<RangeInclusive<u16>>,
+    // With a ring size, lines stay in memory and only the last ones get written, when an error happens
+    ring_size: Option<usize>,
+    ring: VecDeque<String>,
+    cycle: u64,
+}
+
+impl Tracer {
+    // Logs every instruction to out.
+    pub fn new<W: Write + Send + 'static>(out: W) -> Tracer {
+        Tracer {
+            out: Box::new(out),
+            range: None,
+            ring_size: None,
+            ring: VecDeque::new(),
+            cycle: 0,
+        }
+    }
+
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Tracer> {
+        Ok(Tracer::new(BufWriter::new(File::create(path)?)))
+    }
+
+    // Only logs instructions from start to end included. Errors are always logged.
+    pub fn with_range(mut self, start: u16, end: u16) -> Tracer {
+        self.range = Some(start..=end);
+        self
+    }
+
+    // Keeps the last lines in memory instead of writing them,
+    // they're written out if the CPU stops with an error.
+    pub fn with_ring_buffer(mut self, lines: usize) -> Tracer {
+        self.ring_size = Some(lines.max(1));
+        self
+    }
+
+    // Instructions executed since the tracer was attached.
+    pub fn cycles(&self) -> u64 {
+        self.cycle
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    // Runs the next instruction of chip8, logging it.
+    pub(crate) fn step(&mut self, chip8: &mut Chip8) -> Result<(), Chip8Error> {
+        let pc = chip8.program_counter();
+        let bytes = [chip8.opcode_at(pc), chip8.opcode_at(pc.wrapping_add(2))];
+        let (registers, index) = (*chip8.registers(), chip8.index());
+        self.cycle += 1;
+
+        let mut line = format!("{:>8}  {:03X}  {:04X}  ", self.cycle, pc, bytes[0]);
+        match chip8.execute_next() {
+            Ok(instruction) => {
+                if self
+                    .range
+                    .as_ref()
+                    .is_some_and(|range| !range.contains(&pc))
+                {
+                    return Ok(());
+                }
+                let [a, b] = bytes[0].to_be_bytes();
+                let [c, d] = bytes[1].to_be_bytes();
+                let mnemonic = format_instruction(instruction, &[a, b, c, d], Syntax::Octo);
+                let _ = write!(line, "{:<26}", mnemonic);
+                for (register, (old, new)) in registers.iter().zip(chip8.registers()).enumerate() {
+                    if old != new {
+                        let _ = write!(line, "V{:X} {:02X}->{:02X}  ", register, old, new);
+                    }
+                }
+                if index != chip8.index() {
+                    let _ = write!(line, "I {:03X}->{:03X}", index, chip8.index());
+                }
+                self.log(line.trim_end().to_string());
+                Ok(())
+            }
+            Err(e) => {
+                let _ = write!(line, "error: {}", e);
+                self.log(line);
+                self.dump();
+                Err(e)
+            }
+        }
+    }
+
+    fn log(&mut self, line: String) {
+        match self.ring_size {
+            Some(size) => {
+                if self.ring.len() == size {
+                    self.ring.pop_front();
+                }
+                self.ring.push_back(line);
+            }
+            None => {
+                let _ = writeln!(self.out, "{}", line);
+            }
+        }
+    }
+
+    // Writes out whatever the ring buffer holds.
+    fn dump(&mut self) {
+        for line in self.ring.drain(..) {
+            let _ = writeln!(self.out, "{}", line);
+        }
+        let _ = self.out.flush();
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{MEMORY_SIZE_KB, Quirks, XO_MEMORY_SIZE};
+
+// The instruction set the machine understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    // The original CHIP-8, 64x32 display only.
+    #[default]
+    Chip8,
+    // SUPER-CHIP 1.1: 128x64 high resolution mode, scrolling, big font and RPL flags.
+    SuperChip,
+    // XO-CHIP: SUPER-CHIP plus 64K memory, two bit-planes and programmable audio.
+    XoChip,
+}
+
+impl Variant {
+    // Names accepted by `from_name`, in the order they should be shown to users.
+    pub const NAMES: [&'static str; 3] = ["chip8", "schip", "xochip"];
+
+    // Looks up a variant by name, as typed on the command line.
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name.to_ascii_lowercase().as_str() {
+            "chip8" | "chip-8" => Some(Variant::Chip8),
+            "schip" | "superchip" | "super-chip" => Some(Variant::SuperChip),
+            "xochip" | "xo-chip" | "xo" => Some(Variant::XoChip),
+            _ => None,
+        }
+    }
+
+    // Bytes of addressable memory.
+    pub(crate) fn memory_size(self) -> usize {
+        if self == Variant::XoChip {
+            XO_MEMORY_SIZE
+        } else {
+            MEMORY_SIZE_KB
+        }
+    }
+
+    // Stable numbering for save states.
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Variant::Chip8 => 0,
+            Variant::SuperChip => 1,
+            Variant::XoChip => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Variant> {
+        match id {
+            0 => Some(Variant::Chip8),
+            1 => Some(Variant::SuperChip),
+            2 => Some(Variant::XoChip),
+            _ => None,
+        }
+    }
+
+    // The quirks ROMs written for this variant usually expect.
+    // Plain CHIP-8 keeps the emulator's long-standing behavior, --quirks vip asks for the real VIP.
+    pub fn default_quirks(self) -> Quirks {
+        match self {
+            Variant::Chip8 => Quirks::LEGACY,
+            Variant::SuperChip => Quirks::SUPER_CHIP,
+            Variant::XoChip => Quirks::XO_CHIP,
+        }
+    }
+}
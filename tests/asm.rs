@@ -0,0 +1,223 @@
+use rust_8::{
+    Chip8, Quirks, Variant,
+    asm::Assembler,
+    disasm::{Syntax, disassemble_variant},
+};
+use std::{env, fs, process};
+
+fn assemble(source: &str) -> Vec<u8> {
+    Assembler::new().assemble(source).unwrap()
+}
+
+fn error(source: &str) -> String {
+    Assembler::new().assemble(source).unwrap_err().to_string()
+}
+
+#[test]
+fn every_opcode_survives_a_round_trip() {
+    // Classic listings of every instruction assemble back to the same instruction
+    let xo = Assembler::new().with_variant(Variant::XoChip);
+    for opcode in 0..=0xFFFFu16 {
+        let [high, low] = opcode.to_be_bytes();
+        let bytes = [high, low, 0x12, 0x34];
+        let line = &disassemble_variant(&bytes, 0x200, Variant::XoChip)[0];
+        if !line.is_code() {
+            continue;
+        }
+
+        let source = line.mnemonic(Syntax::Classic);
+        let image = xo
+            .assemble(&source)
+            .unwrap_or_else(|e| panic!("{:04X} {}: {}", opcode, source, e));
+        let again = &disassemble_variant(&image, 0x200, Variant::XoChip)[0];
+        assert_eq!(again.mnemonic(Syntax::Classic), source, "{:04X}", opcode);
+    }
+}
+
+#[test]
+fn labels_can_be_used_before_they_are_defined() {
+    let image = assemble(
+        "start:  CALL routine
+                 JP start
+         routine: ADD V0, 1
+                  RET",
+    );
+    assert_eq!(image, [0x22, 0x04, 0x12, 0x00, 0x70, 0x01, 0x00, 0xEE]);
+}
+
+#[test]
+fn constants_and_expressions() {
+    let image = assemble(
+        "SPEED EQU 3
+         FAST  EQU SPEED + SPEED",
+    );
+    assert!(image.is_empty());
+
+    let image = assemble(
+        "SPEED EQU 3   ; pixels per frame
+         LD V0, SPEED+1
+         LD V1, -1
+         LD V2, %1010
+         LD V3, 'A'
+         LD I, table + 2
+         table: DW #1234, table",
+    );
+    assert_eq!(
+        image,
+        [
+            0x60, 0x04, 0x61, 0xFF, 0x62, 0x0A, 0x63, 0x41, 0xA2, 0x0C, 0x12, 0x34, 0x02, 0x0A
+        ]
+    );
+}
+
+#[test]
+fn data_directives() {
+    let image = assemble(
+        "DB 1, #02, $03, 0x04, \"Hi;\"
+         SPRITE .##....., #######.
+         SPRITE ##............##",
+    );
+    assert_eq!(
+        image,
+        [1, 2, 3, 4, b'H', b'i', b';', 0x60, 0xFE, 0xC0, 0x03]
+    );
+}
+
+#[test]
+fn org_pads_with_zeros() {
+    let image = assemble(
+        "CLS
+         ORG #208
+         data: DB #FF",
+    );
+    assert_eq!(image, [0x00, 0xE0, 0, 0, 0, 0, 0, 0, 0xFF]);
+    assert!(error("DB 1, 2\nORG #200").starts_with("<source>:2: ORG"));
+}
+
+#[test]
+fn long_index_on_xo_chip() {
+    let xo = Assembler::new().with_variant(Variant::XoChip);
+    let image = xo.assemble("LD I, LONG end\nend: EXIT").unwrap();
+    assert_eq!(image, [0xF0, 0x00, 0x02, 0x04, 0x00, 0xFD]);
+}
+
+#[test]
+fn non_ascii_operands_are_errors_not_panics() {
+    assert_eq!(
+        error("LD I, ab€"),
+        "<source>:1: unknown name or bad number 'ab€'"
+    );
+    assert!(error("LD I, lo€ng").starts_with("<source>:1:"));
+    // Sprite rows are counted in characters, whatever their size in bytes
+    assert_eq!(assemble("SPRITE ██......, █.█"), [0xC0, 0xA0]);
+    assert_eq!(
+        error("SPRITE ██████████"),
+        "<source>:1: sprite rows are up to 8 or 16 pixels wide, not '██████████'"
+    );
+}
+
+#[test]
+fn errors_have_line_numbers() {
+    assert_eq!(
+        error("CLS\n\nJP nowhere"),
+        "<source>:3: unknown name or bad number 'nowhere'"
+    );
+    assert_eq!(error("  FOO V1"), "<source>:1: unknown instruction FOO");
+    assert_eq!(error("ADD I, 3"), "<source>:1: bad operands for ADD: I, 3");
+    assert_eq!(error("LD V0, 256"), "<source>:1: 256 doesn't fit in a byte");
+    assert_eq!(
+        error("a: CLS\na: CLS"),
+        "<source>:2: 'a' is already defined"
+    );
+    assert_eq!(error("V1: CLS"), "<source>:1: 'V1' is a reserved name");
+}
+
+#[test]
+fn instructions_are_checked_against_the_variant() {
+    assert_eq!(
+        error("HIGH"),
+        "<source>:1: HIGH isn't part of the CHIP-8 instruction set"
+    );
+    let schip = Assembler::new().with_variant(Variant::SuperChip);
+    assert_eq!(schip.assemble("HIGH").unwrap(), [0x00, 0xFF]);
+    assert!(schip.assemble("PLANE 2").is_err());
+}
+
+#[test]
+fn jump_offsets_follow_the_jump_quirk() {
+    assert_eq!(assemble("JP V0, #345"), [0xB3, 0x45]);
+    assert_eq!(
+        error("JP V3, #345"),
+        "<source>:1: bad operands for JP: V3, #345"
+    );
+
+    // CHIP-48 and SUPER-CHIP jump to XNN + VX
+    let schip = Assembler::new().with_variant(Variant::SuperChip);
+    assert_eq!(schip.assemble("JP V3, #345").unwrap(), [0xB3, 0x45]);
+    assert_eq!(
+        schip.assemble("JP V3, #245").unwrap_err().to_string(),
+        "<source>:1: JP V3 can only jump to 300-3FF, not 0x245"
+    );
+    assert!(schip.assemble("JP V0, #345").is_err());
+    let chip48 = Assembler::new().with_quirks(Quirks::CHIP_48);
+    assert_eq!(chip48.assemble("JP VA, #A00").unwrap(), [0xBA, 0x00]);
+
+    // And runs as written
+    let rom = schip
+        .assemble("LD V3, 4\nJP V3, #300\nORG #304\nLD V0, 1\nJP #306")
+        .unwrap();
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::SuperChip)
+        .load_rom_bytes(&rom)
+        .unwrap();
+    chip8.run(3).unwrap();
+    assert_eq!(chip8.registers()[0], 1);
+}
+
+#[test]
+fn includes_are_relative_to_the_including_file() {
+    let directory = env::temp_dir().join(format!("rust-8-asm-{}", process::id()));
+    fs::create_dir_all(directory.join("lib")).unwrap();
+    fs::write(
+        directory.join("main.s"),
+        "JP start\nINCLUDE \"lib/sprites.s\"\nstart: LD I, ball",
+    )
+    .unwrap();
+    fs::write(directory.join("lib/sprites.s"), "ball: DB #3C\n  BAD").unwrap();
+
+    let error = Assembler::new()
+        .assemble_file(directory.join("main.s"))
+        .unwrap_err();
+    assert_eq!(error.line, 2);
+    assert!(error.file.ends_with("sprites.s"), "{}", error.file);
+
+    fs::write(directory.join("lib/sprites.s"), "ball: DB #3C").unwrap();
+    let image = Assembler::new()
+        .assemble_file(directory.join("main.s"))
+        .unwrap();
+    assert_eq!(image, [0x12, 0x03, 0x3C, 0xA2, 0x02]);
+
+    fs::write(directory.join("lib/sprites.s"), "INCLUDE \"../main.s\"").unwrap();
+    let error = Assembler::new()
+        .assemble_file(directory.join("main.s"))
+        .unwrap_err();
+    assert!(error.message.contains("includes itself"), "{}", error);
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn assembled_programs_run() {
+    let image = assemble(
+        "       LD V0, 10
+                LD V1, 0
+         loop:  ADD V1, 3
+                ADD V0, -1
+                SE V0, 0
+                JP loop
+         done:  JP done",
+    );
+    let mut chip8 = Chip8::new().load_rom_bytes(&image).unwrap();
+    chip8.run(100).unwrap();
+    assert_eq!(chip8.registers()[1], 30);
+}
@@ -0,0 +1,189 @@
+use rust_8::{
+    Chip8, Variant,
+    audio::{AudioSink, NullSink, WavSink, pattern_rate},
+};
+use std::sync::{Arc, Mutex};
+use std::{env, fs, process};
+
+// Writes down what the core sends, the test keeps a handle on it
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl Recorder {
+    fn events(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl AudioSink for Recorder {
+    fn beeper(&mut self, on: bool) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(if on { "on" } else { "off" }.to_string());
+    }
+
+    fn samples(&mut self, samples: &[f32]) {
+        let lit = samples.iter().filter(|&&sample| sample > 0.0).count();
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{} samples, {} lit", samples.len(), lit));
+    }
+
+    fn end_frame(&mut self) {
+        self.0.lock().unwrap().push("frame".to_string());
+    }
+}
+
+const BEEP: [u8; 6] = [
+    0x60, 0x02, // 200: v0 := 2
+    0xF0, 0x18, // 202: buzzer := v0
+    0x12, 0x04, // 204: jump 204
+];
+
+fn beeping<S: AudioSink + 'static>(sink: S) -> Chip8 {
+    let mut chip8 = Chip8::new()
+        .with_audio_sink(sink)
+        .load_rom_bytes(&BEEP)
+        .unwrap();
+    chip8.run(3).unwrap();
+    chip8
+}
+
+#[test]
+fn the_beeper_follows_the_sound_timer() {
+    let recorder = Recorder::default();
+    let mut chip8 = beeping(recorder.clone());
+    assert!(chip8.beeper());
+    for _ in 0..4 {
+        chip8.tick_timers();
+    }
+    assert!(!chip8.beeper());
+    assert_eq!(
+        recorder.events(),
+        ["on", "frame", "frame", "off", "frame", "frame"]
+    );
+}
+
+#[test]
+fn xo_chip_patterns_are_rendered_to_samples() {
+    let mut rom = vec![
+        0xA2, 0x0C, // 200: i := 0x20C
+        0xF0, 0x02, // 202: audio
+        0x60, 0x01, // 204: v0 := 1
+        0xF0, 0x18, // 206: buzzer := v0
+        0x12, 0x08, // 208: jump 208
+        0x00, 0x00,
+    ];
+    // Half the pattern lit
+    rom.extend([0xFF; 8]);
+    rom.extend([0x00; 8]);
+
+    let recorder = Recorder::default();
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::XoChip)
+        .with_audio_sink(recorder.clone())
+        .load_rom_bytes(&rom)
+        .unwrap();
+    chip8.run(5).unwrap();
+    chip8.tick_timers();
+
+    // The default pitch plays 4000 bits a second: 64 lit bits last 64 / 4000 s
+    assert_eq!(pattern_rate(64), 4000.0);
+    let lit = (64.0 * 44_100.0 / 4000.0f64).ceil() as usize;
+    assert_eq!(
+        recorder.events(),
+        [
+            "on".to_string(),
+            format!("735 samples, {} lit", lit),
+            "frame".to_string()
+        ]
+    );
+    assert_eq!(pattern_rate(112), 8000.0);
+
+    // Starting over mid-beep stops it, and the pattern plays from its first bit again
+    let first = recorder.events();
+    chip8.reload_rom(&rom).unwrap();
+    chip8.run(5).unwrap();
+    chip8.tick_timers();
+    let events = recorder.events();
+    assert_eq!(events[3], "off");
+    assert_eq!(events[4..], first);
+}
+
+#[test]
+fn wav_files_hold_the_beep() {
+    let path = env::temp_dir().join(format!("rust-8-audio-{}.wav", process::id()));
+    let sink = WavSink::create(&path)
+        .unwrap()
+        .with_tone(1000.0)
+        .with_volume(0.25);
+    let mut chip8 = beeping(sink);
+    for _ in 0..3 {
+        chip8.tick_timers();
+    }
+    // Dropping the sink finishes the file
+    drop(chip8.take_audio_sink());
+
+    let wav = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 44_100);
+    assert_eq!(&wav[36..40], b"data");
+    let size = u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize;
+    assert_eq!(size, 3 * 735 * 2);
+    assert_eq!(wav.len(), 44 + size);
+
+    let samples: Vec<i16> = wav[44..]
+        .chunks(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    // Two frames of square wave at a quarter of full scale, then silence
+    let peak = (i16::MAX as f32 * 0.25) as i16;
+    assert!(samples[..2 * 735].iter().all(|&s| s == peak || s == -peak));
+    assert!(samples[..2 * 735].contains(&-peak));
+    assert!(samples[2 * 735..].iter().all(|&s| s == 0));
+}
+
+#[test]
+fn resetting_mid_beep_turns_the_beeper_off() {
+    let recorder = Recorder::default();
+    let mut chip8 = beeping(recorder.clone());
+    chip8.tick_timers();
+    chip8.reset();
+    assert!(!chip8.beeper());
+    assert_eq!(recorder.events(), ["on", "frame", "off"]);
+
+    // The next beep is heard too
+    chip8.reload_rom(&BEEP).unwrap();
+    chip8.run(3).unwrap();
+    chip8.tick_timers();
+    assert_eq!(recorder.events(), ["on", "frame", "off", "on", "frame"]);
+}
+
+#[test]
+fn loading_a_state_keeps_the_sink() {
+    let recorder = Recorder::default();
+    let mut chip8 = Chip8::new()
+        .with_audio_sink(recorder.clone())
+        .load_rom_bytes(&BEEP)
+        .unwrap();
+    let state = chip8.save_state();
+    chip8.run(3).unwrap();
+    chip8.tick_timers();
+    // Back to before the beep, the sink hears it stop
+    chip8.load_state(&state).unwrap();
+    chip8.tick_timers();
+    assert_eq!(recorder.events(), ["on", "frame", "off", "frame"]);
+}
+
+#[test]
+fn the_null_sink_runs_silently() {
+    let mut chip8 = beeping(NullSink);
+    for _ in 0..10 {
+        chip8.tick_timers();
+    }
+    assert_eq!(chip8.sound_timer(), 0);
+}
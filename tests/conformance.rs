@@ -0,0 +1,166 @@
+// Runs the test ROMs in test_roms/ under every quirk preset and compares the screen they end on
+// to the preset's snapshot in tests/snapshots/. Run with UPDATE_SNAPSHOTS=1 to write the snapshots again.
+
+use rust_8::{Chip8, Quirks, assert_screen_eq, octo::Compiler};
+use std::{env, fs, path::PathBuf};
+
+const PRESETS: [(&str, Quirks); 4] = [
+    ("vip", Quirks::COSMAC_VIP),
+    ("chip48", Quirks::CHIP_48),
+    ("schip", Quirks::SUPER_CHIP),
+    ("xochip", Quirks::XO_CHIP),
+];
+
+// ROM, snapshot name and how many frames the ROM needs to finish drawing, even waiting for vblank on every sprite.
+// Every preset has its own snapshot, {name}-{preset}.txt.
+// TODO: the rest of the Timendus suite (3-corax+, 4-flags, 5-quirks, 6-keypad, 7-beep) isn't in test_roms/,
+// so it isn't covered yet. The quirks ROM also asks for the platform before it shows anything.
+const ROMS: [(&str, &str, usize); 2] = [
+    ("1-ibm-logo.ch8", "ibm-logo", 60),
+    ("test_opcode.ch8", "test-opcode", 200),
+];
+
+fn manifest_path(relative: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+// Same frame loop as the headless runner
+fn run_frames(mut chip8: Chip8, frames: usize) -> Chip8 {
+    for _ in 0..frames {
+        chip8.run(1000).unwrap();
+        chip8.tick_timers();
+    }
+    chip8
+}
+
+#[test]
+fn roms_match_their_snapshots() {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    for (rom, snapshot, frames) in ROMS {
+        for (preset, quirks) in PRESETS {
+            let snapshot_path =
+                manifest_path(&format!("tests/snapshots/{}-{}.txt", snapshot, preset));
+            let chip8 = Chip8::new()
+                .with_quirks(quirks)
+                .with_seed(1)
+                .load_rom(manifest_path(&format!("test_roms/{}", rom)))
+                .unwrap();
+            let screen = run_frames(chip8, frames).framebuffer().to_ascii();
+
+            if update {
+                fs::write(&snapshot_path, &screen).unwrap();
+                continue;
+            }
+            let expected = fs::read_to_string(&snapshot_path)
+                .unwrap_or_else(|e| panic!("{}: {}", snapshot_path.display(), e));
+            assert_screen_eq!(
+                screen,
+                expected,
+                "{} with {} quirks, {}",
+                rom,
+                preset,
+                snapshot_path.display()
+            );
+        }
+    }
+}
+
+// What each preset is expected to do, field by field
+#[test]
+fn presets_have_the_documented_quirks() {
+    let table = |q: Quirks| {
+        [
+            q.shift_uses_vy,
+            q.memory_increments_i,
+            q.jump_uses_vx,
+            q.vf_reset,
+            q.clip_sprites,
+            q.display_wait,
+        ]
+    };
+    assert_eq!(
+        table(Quirks::COSMAC_VIP),
+        [true, true, false, true, true, true]
+    );
+    assert_eq!(
+        table(Quirks::CHIP_48),
+        [false, false, true, false, true, false]
+    );
+    assert_eq!(table(Quirks::SUPER_CHIP), table(Quirks::CHIP_48));
+    assert_eq!(
+        table(Quirks::XO_CHIP),
+        [true, true, false, false, false, false]
+    );
+}
+
+// Small programs in the spirit of the Timendus quirks test, one per quirk.
+// Each ends on `: done jump done`.
+fn run_octo(source: &str, quirks: Quirks) -> Chip8 {
+    let image = Compiler::new().compile(source).unwrap();
+    let mut chip8 = Chip8::new()
+        .with_quirks(quirks)
+        .load_rom_bytes(&image)
+        .unwrap();
+    for _ in 0..1000 {
+        let pc = chip8.program_counter();
+        if chip8.opcode_at(pc) == 0x1000 | pc {
+            return chip8;
+        }
+        if !chip8.step().unwrap() {
+            chip8.tick_timers();
+        }
+    }
+    panic!("the program never finished");
+}
+
+#[test]
+fn every_preset_behaves_like_its_quirks() {
+    for (preset, quirks) in PRESETS {
+        let chip8 = run_octo(": main vf := 5 v0 := 1 v0 |= v0 : done jump done", quirks);
+        let expected = if quirks.vf_reset { 0 } else { 5 };
+        assert_eq!(chip8.registers()[0xF], expected, "vf reset, {}", preset);
+
+        let chip8 = run_octo(": main i := 0x300 save v1 : done jump done", quirks);
+        let expected = if quirks.memory_increments_i {
+            0x302
+        } else {
+            0x300
+        };
+        assert_eq!(chip8.index(), expected, "memory, {}", preset);
+
+        let chip8 = run_octo(": main v0 := 1 v1 := 4 v0 >>= v1 : done jump done", quirks);
+        let expected = if quirks.shift_uses_vy { 2 } else { 0 };
+        assert_eq!(chip8.registers()[0], expected, "shifting, {}", preset);
+
+        // jump0 0x20E lands on the first or second `v3 := ...` depending on the register it adds
+        let chip8 = run_octo(
+            ": main v0 := 0 v2 := 4 jump0 0x20E
+             :org 0x20E v3 := 1 jump done v3 := 2 : done jump done",
+            quirks,
+        );
+        let expected = if quirks.jump_uses_vx { 2 } else { 1 };
+        assert_eq!(chip8.registers()[3], expected, "jumping, {}", preset);
+
+        // An 8 pixel wide sprite 4 pixels from the right edge
+        let chip8 = run_octo(
+            ": main i := bar v0 := 60 v1 := 0 sprite v0 v1 1 : done jump done : bar 0xFF",
+            quirks,
+        );
+        let frame = chip8.framebuffer();
+        assert_eq!(frame.pixel(63, 0), 1, "clipping, {}", preset);
+        let expected = u8::from(!quirks.clip_sprites);
+        assert_eq!(frame.pixel(0, 0), expected, "clipping, {}", preset);
+
+        // Nothing runs after a sprite until the next vblank
+        let mut chip8 = Chip8::new()
+            .with_quirks(quirks)
+            .load_rom_bytes(&[0xD0, 0x01, 0x60, 0x01, 0x12, 0x04])
+            .unwrap();
+        chip8.run(2).unwrap();
+        let expected = if quirks.display_wait { 0 } else { 1 };
+        assert_eq!(chip8.registers()[0], expected, "display wait, {}", preset);
+        chip8.tick_timers();
+        chip8.run(1).unwrap();
+        assert_eq!(chip8.registers()[0], 1, "display wait, {}", preset);
+    }
+}
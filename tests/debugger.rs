@@ -0,0 +1,267 @@
+use rust_8::{Chip8, Debugger, StopReason, WatchHit, Watchpoint};
+
+// A call two levels deep, then spin
+const NESTED_CALLS: [u8; 18] = [
+    0x22, 0x08, // 200: call 208
+    0x60, 0x01, // 202: v0 := 1
+    0x12, 0x04, // 204: jump 204
+    0x00, 0x00, //
+    0x61, 0x02, // 208: v1 := 2
+    0x22, 0x0E, // 20A: call 20E
+    0x00, 0xEE, // 20C: return
+    0x62, 0x03, // 20E: v2 := 3
+    0x00, 0xEE, // 210: return
+];
+
+fn nested_calls() -> Chip8 {
+    Chip8::new().load_rom_bytes(&NESTED_CALLS).unwrap()
+}
+
+#[test]
+fn step_runs_one_instruction() {
+    let mut chip8 = nested_calls();
+    let mut debugger = Debugger::new();
+
+    assert_eq!(debugger.step(&mut chip8).unwrap(), StopReason::Done);
+    assert_eq!(chip8.program_counter(), 0x208);
+    assert_eq!(chip8.stack(), &[0x202]);
+}
+
+#[test]
+fn step_over_runs_the_whole_call() {
+    let mut chip8 = nested_calls();
+    let mut debugger = Debugger::new();
+
+    assert_eq!(
+        debugger.step_over(&mut chip8, 100).unwrap(),
+        StopReason::Done
+    );
+    assert_eq!(chip8.program_counter(), 0x202);
+    assert_eq!(chip8.registers()[1..3], [2, 3]);
+    assert!(chip8.stack().is_empty());
+}
+
+#[test]
+fn step_over_stops_at_breakpoints_inside_the_call() {
+    let mut chip8 = nested_calls();
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(0x20E);
+
+    assert_eq!(
+        debugger.step_over(&mut chip8, 100).unwrap(),
+        StopReason::Breakpoint(0x20E)
+    );
+    assert_eq!(chip8.stack(), &[0x202, 0x20C]);
+}
+
+#[test]
+fn finish_runs_to_the_caller() {
+    let mut chip8 = nested_calls();
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(0x20E);
+    debugger.run(&mut chip8, 100).unwrap();
+
+    assert_eq!(
+        debugger.finish(&mut chip8, 100).unwrap(),
+        StopReason::Returned
+    );
+    assert_eq!(chip8.program_counter(), 0x20C);
+    assert_eq!(chip8.stack(), &[0x202]);
+
+    assert_eq!(
+        debugger.finish(&mut chip8, 100).unwrap(),
+        StopReason::Returned
+    );
+    assert_eq!(chip8.program_counter(), 0x202);
+}
+
+#[test]
+fn run_continues_past_the_current_breakpoint() {
+    let mut chip8 = nested_calls();
+    let mut debugger = Debugger::new();
+    debugger.add_breakpoint(0x204);
+
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::Breakpoint(0x204)
+    );
+    assert_eq!(chip8.registers()[0], 1);
+
+    // 204 jumps to itself, so the next stop is the same breakpoint
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::Breakpoint(0x204)
+    );
+
+    assert!(debugger.remove_breakpoint(0x204));
+    assert_eq!(debugger.run(&mut chip8, 100).unwrap(), StopReason::Limit);
+}
+
+#[test]
+fn display_wait_does_not_stall_stepping() {
+    // The VIP quirks stop after every draw until the next frame
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[0xD0, 0x05, 0xD0, 0x05, 0x60, 0x07])
+        .unwrap();
+    let mut debugger = Debugger::new();
+
+    for _ in 0..3 {
+        assert_eq!(debugger.step(&mut chip8).unwrap(), StopReason::Done);
+    }
+    assert_eq!(chip8.registers()[0], 7);
+}
+
+#[test]
+fn waiting_for_a_key_stops_the_run() {
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[0xF3, 0x0A, 0x12, 0x02])
+        .unwrap();
+    let mut debugger = Debugger::new();
+
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::WaitingForKey
+    );
+    // FX0A finishes when the key is let go
+    chip8.key_down(0xB);
+    assert_eq!(
+        debugger.step(&mut chip8).unwrap(),
+        StopReason::WaitingForKey
+    );
+    chip8.key_up(0xB);
+    assert_eq!(debugger.step(&mut chip8).unwrap(), StopReason::Done);
+    assert_eq!(chip8.registers()[3], 0xB);
+}
+
+#[test]
+fn state_dump_shows_registers_and_stack() {
+    let mut chip8 = nested_calls();
+    let mut debugger = Debugger::new();
+    debugger.step(&mut chip8).unwrap();
+    debugger.step(&mut chip8).unwrap();
+
+    let dump = Debugger::dump_state(&chip8);
+    assert!(dump.starts_with("PC 20A  opcode 220E"), "{}", dump);
+    assert!(dump.contains("V1 02"), "{}", dump);
+    assert!(dump.contains("Stack: 202"), "{}", dump);
+}
+
+// Score keeping: BCD of v0 at 0x300, then v0-v1 stored at 0x310
+const SCORE: [u8; 14] = [
+    0x60, 0x7B, // 200: v0 := 123
+    0xA3, 0x00, // 202: i := 300
+    0xF0, 0x33, // 204: bcd v0
+    0xA3, 0x10, // 206: i := 310
+    0x61, 0x00, // 208: v1 := 0
+    0xF1, 0x55, // 20A: save v1
+    0x12, 0x0C, // 20C: jump 20C
+];
+
+fn score() -> Chip8 {
+    Chip8::new().load_rom_bytes(&SCORE).unwrap()
+}
+
+#[test]
+fn memory_watchpoints_report_the_write() {
+    let mut chip8 = score();
+    let mut debugger = Debugger::new();
+    debugger.add_watchpoint(Watchpoint::Memory {
+        start: 0x301,
+        end: 0x30F,
+    });
+
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::Watchpoint(WatchHit::Memory {
+            pc: 0x204,
+            address: 0x301,
+            old: 0,
+            new: 2
+        })
+    );
+    assert_eq!(chip8.program_counter(), 0x206);
+    assert_eq!(debugger.run(&mut chip8, 100).unwrap(), StopReason::Limit);
+}
+
+#[test]
+fn memory_watchpoints_fire_on_writes_of_the_same_value() {
+    let mut chip8 = score();
+    let mut debugger = Debugger::new();
+    debugger.add_watchpoint(Watchpoint::Memory {
+        start: 0x311,
+        end: 0x311,
+    });
+
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::Watchpoint(WatchHit::Memory {
+            pc: 0x20A,
+            address: 0x311,
+            old: 0,
+            new: 0
+        })
+    );
+}
+
+#[test]
+fn register_watchpoints_fire_on_changes_only() {
+    let mut chip8 = score();
+    let mut debugger = Debugger::new();
+    assert!(debugger.add_watchpoint(Watchpoint::Register(1)));
+    assert!(!debugger.add_watchpoint(Watchpoint::Register(1)));
+    assert!(!debugger.add_watchpoint(Watchpoint::Register(16)));
+    assert!(debugger.add_watchpoint(Watchpoint::Register(0)));
+
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::Watchpoint(WatchHit::Register {
+            pc: 0x200,
+            register: 0,
+            old: 0,
+            new: 123
+        })
+    );
+    // v1 := 0 leaves v1 alone
+    assert_eq!(debugger.run(&mut chip8, 100).unwrap(), StopReason::Limit);
+}
+
+#[test]
+fn index_watchpoints_fire_on_changes() {
+    let mut chip8 = score();
+    let mut debugger = Debugger::new();
+    debugger.add_watchpoint(Watchpoint::Index);
+
+    debugger.run(&mut chip8, 100).unwrap();
+    assert_eq!(
+        debugger.run(&mut chip8, 100).unwrap(),
+        StopReason::Watchpoint(WatchHit::Index {
+            pc: 0x206,
+            old: 0x300,
+            new: 0x310
+        })
+    );
+
+    assert!(debugger.remove_watchpoint(Watchpoint::Index));
+    assert_eq!(debugger.watchpoints().count(), 0);
+}
+
+#[test]
+fn frames_are_as_long_as_in_run_frame() {
+    // v0 := 5, delay := v0, then spin
+    let rom = [0x60, 0x05, 0xF0, 0x15, 0x12, 0x04];
+    let mut stepped = Chip8::new().load_rom_bytes(&rom).unwrap();
+    let mut framed = Chip8::new().load_rom_bytes(&rom).unwrap();
+    let mut debugger = Debugger::new();
+
+    for _ in 0..3 {
+        framed.run_frame().unwrap();
+        // The timers tick on the frame's last instruction, not before
+        for _ in 1..framed.cycles_per_frame() {
+            debugger.step(&mut stepped).unwrap();
+        }
+        assert_eq!(stepped.delay_timer(), framed.delay_timer() + 1);
+        debugger.step(&mut stepped).unwrap();
+        assert_eq!(stepped.delay_timer(), framed.delay_timer());
+    }
+    assert_eq!(framed.delay_timer(), 2);
+}
@@ -0,0 +1,97 @@
+use rust_8::{
+    Variant,
+    disasm::{Syntax, disassemble, disassemble_variant},
+};
+
+fn listing(bytes: &[u8], syntax: Syntax) -> Vec<String> {
+    disassemble(bytes, 0x200)
+        .iter()
+        .map(|line| format!("{:03X} {}", line.address, line.mnemonic(syntax)))
+        .collect()
+}
+
+#[test]
+fn ibm_logo_sprites_are_data() {
+    let rom = include_bytes!("../test_roms/1-ibm-logo.ch8");
+    let lines = disassemble(rom, 0x200);
+
+    let code: Vec<_> = lines.iter().filter(|line| line.is_code()).collect();
+    assert_eq!(code.len(), 21);
+    assert_eq!(code[0].opcode(), Some(0x00E0));
+    assert_eq!(code[20].mnemonic(Syntax::Octo), "jump 0x228");
+
+    let data = lines.iter().find(|line| !line.is_code()).unwrap();
+    assert_eq!(data.address, 0x22A);
+    assert_eq!(data.opcode(), None);
+    assert_eq!(
+        lines.iter().map(|line| line.bytes.len()).sum::<usize>(),
+        rom.len()
+    );
+}
+
+#[test]
+fn skips_follow_both_paths() {
+    let rom = [
+        0x30, 0x01, // 200: skip if v0 == 1
+        0x12, 0x08, // 202: jump 208
+        0x00, 0xEE, // 204: return
+        0xFF, 0xFF, // 206: data
+        0x00, 0xEE, // 208: return
+    ];
+    assert_eq!(
+        listing(&rom, Syntax::Octo),
+        [
+            "200 if v0 != 0x01 then",
+            "202 jump 0x208",
+            "204 return",
+            "206 0xFF 0xFF",
+            "208 return",
+        ]
+    );
+}
+
+#[test]
+fn calls_are_followed_and_the_caller_continues() {
+    let rom = [
+        0x22, 0x06, // 200: call 206
+        0x12, 0x02, // 202: jump 202
+        0x01, 0x02, // 204: data
+        0x6A, 0x05, // 206: va := 5
+        0x8A, 0xB4, // 208: va += vb
+        0x00, 0xEE, // 20A: return
+    ];
+    assert_eq!(
+        listing(&rom, Syntax::Classic),
+        [
+            "200 CALL #206",
+            "202 JP #202",
+            "204 DB #01, #02",
+            "206 LD VA, #05",
+            "208 ADD VA, VB",
+            "20A RET",
+        ]
+    );
+}
+
+#[test]
+fn long_index_takes_four_bytes() {
+    let rom = [0xF0, 0x00, 0x12, 0x34, 0x00, 0xFD];
+    assert_eq!(
+        listing(&rom, Syntax::Octo),
+        ["200 i := long 0x1234", "204 exit"]
+    );
+
+    // Plain CHIP-8 doesn't know F000 or 00FD, so it's all data
+    let lines = disassemble_variant(&rom, 0x200, Variant::Chip8);
+    assert_eq!(lines.len(), 1);
+    assert!(!lines[0].is_code());
+}
+
+#[test]
+fn base_address_moves_the_listing() {
+    let rom = [0x16, 0x00];
+    assert_eq!(
+        disassemble(&rom, 0x600)[0].mnemonic(Syntax::Classic),
+        "JP #600"
+    );
+}
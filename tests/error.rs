@@ -0,0 +1,79 @@
+use rust_8::{Chip8, Chip8Error};
+use std::{error::Error, io};
+
+#[test]
+fn errors_describe_themselves() {
+    let cases = [
+        (
+            Chip8Error::UnknownOpcode {
+                pc: 0x200,
+                opcode: 0xFFFF,
+            },
+            "unknown opcode 0xFFFF at 0x200",
+        ),
+        (
+            Chip8Error::StackOverflow { pc: 0x20A },
+            "stack overflow at 0x20A",
+        ),
+        (
+            Chip8Error::StackUnderflow { pc: 0x20A },
+            "stack underflow at 0x20A",
+        ),
+        (
+            Chip8Error::MemoryOutOfBounds {
+                pc: 0x202,
+                address: 0x1000,
+            },
+            "memory access out of bounds at 0x202 (address 0x1000)",
+        ),
+        (
+            Chip8Error::RomTooLarge {
+                size: 3585,
+                max: 3584,
+            },
+            "ROM is 3585 bytes, at most 3584 fit in memory",
+        ),
+        (
+            Chip8Error::InvalidSaveState("bad magic"),
+            "invalid save state: bad magic",
+        ),
+    ];
+    for (error, message) in cases {
+        assert_eq!(error.to_string(), message);
+        // Only I/O errors wrap another one
+        assert!(error.source().is_none());
+    }
+}
+
+#[test]
+fn io_errors_keep_their_source() {
+    let error = Chip8Error::from(io::Error::new(io::ErrorKind::NotFound, "no rom"));
+    assert!(matches!(&error, Chip8Error::Io(e) if e.kind() == io::ErrorKind::NotFound));
+    assert_eq!(error.to_string(), "I/O error: no rom");
+    assert_eq!(error.source().unwrap().to_string(), "no rom");
+
+    match Chip8::new().load_rom("no/such/rom.ch8") {
+        Err(error @ Chip8Error::Io(_)) => {
+            let source = error.source().unwrap().downcast_ref::<io::Error>().unwrap();
+            assert_eq!(source.kind(), io::ErrorKind::NotFound);
+        }
+        _ => panic!("expected Io"),
+    }
+}
+
+#[test]
+fn access_past_memory_reports_where_it_happened() {
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[
+            0xAF, 0xFF, // 200: i := 0xFFF
+            0xF1, 0x55, // 202: save v1
+        ])
+        .unwrap();
+    match chip8.run(2) {
+        Err(Chip8Error::MemoryOutOfBounds { pc, address }) => {
+            assert_eq!(pc, 0x202);
+            assert_eq!(address, 0x1000);
+        }
+        _ => panic!("expected MemoryOutOfBounds"),
+    }
+}
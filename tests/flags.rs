@@ -0,0 +1,47 @@
+use rust_8::Chip8;
+
+// v[x] := a, v[y] := b, then op; returns the registers after it ran
+fn run(x: u8, a: u8, y: u8, b: u8, op: u16) -> [u8; 16] {
+    let [high, low] = op.to_be_bytes();
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[0x60 | x, a, 0x60 | y, b, high, low])
+        .unwrap();
+    chip8.run(3).unwrap();
+    *chip8.registers()
+}
+
+#[test]
+fn arithmetic_sets_the_flag() {
+    // 8014: v0 += v1
+    let v = run(0, 200, 1, 100, 0x8014);
+    assert_eq!((v[0], v[0xF]), (44, 1));
+    let v = run(0, 2, 1, 3, 0x8014);
+    assert_eq!((v[0], v[0xF]), (5, 0));
+    // 8015: v0 -= v1, the flag is set when there's no borrow
+    let v = run(0, 5, 1, 3, 0x8015);
+    assert_eq!((v[0], v[0xF]), (2, 1));
+    let v = run(0, 3, 1, 5, 0x8015);
+    assert_eq!((v[0], v[0xF]), (254, 0));
+    // 8017: v0 := v1 - v0
+    let v = run(0, 3, 1, 5, 0x8017);
+    assert_eq!((v[0], v[0xF]), (2, 1));
+    let v = run(0, 5, 1, 3, 0x8017);
+    assert_eq!((v[0], v[0xF]), (254, 0));
+}
+
+// With vF as the destination the flag is written last, so it's what stays in vF
+#[test]
+fn the_flag_wins_over_the_result_in_vf() {
+    // 8FF4: vF += vF, 0x90 + 0x90 carries
+    assert_eq!(run(0xF, 0x90, 0xF, 0x90, 0x8FF4)[0xF], 1);
+    assert_eq!(run(0xF, 0x10, 0xF, 0x10, 0x8FF4)[0xF], 0);
+    // 8F05: vF -= v0
+    assert_eq!(run(0xF, 9, 0, 3, 0x8F05)[0xF], 1);
+    assert_eq!(run(0xF, 3, 0, 9, 0x8F05)[0xF], 0);
+    // 8FF5 and 8FF7 never borrow
+    assert_eq!(run(0xF, 7, 0xF, 7, 0x8FF5)[0xF], 1);
+    assert_eq!(run(0xF, 7, 0xF, 7, 0x8FF7)[0xF], 1);
+    // 8F07: vF := v0 - vF
+    assert_eq!(run(0xF, 3, 0, 9, 0x8F07)[0xF], 1);
+    assert_eq!(run(0xF, 9, 0, 3, 0x8F07)[0xF], 0);
+}
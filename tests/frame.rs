@@ -0,0 +1,146 @@
+use rust_8::{Chip8, FrameClock, FrameResult, Timing, VIP_CYCLES_PER_FRAME, Variant};
+use std::time::{Duration, Instant};
+
+const PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+#[test]
+fn frames_run_their_instructions_then_tick() {
+    let mut chip8 = Chip8::new()
+        .with_cycles_per_frame(10)
+        .load_rom_bytes(&[
+            0x61, 0x05, // 200: v1 := 5
+            0xF1, 0x15, // 202: delay := v1
+            0x70, 0x01, // 204: v0 += 1
+            0x12, 0x04, // 206: jump 204
+        ])
+        .unwrap();
+    assert_eq!(chip8.cycles_per_frame(), 10);
+
+    let frame = chip8.run_frame().unwrap();
+    assert_eq!(frame, FrameResult::default());
+    assert_eq!(chip8.registers()[0], 4);
+    assert_eq!(chip8.delay_timer(), 4);
+
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.registers()[0], 9);
+    assert_eq!(chip8.delay_timer(), 3);
+}
+
+#[test]
+fn results_tell_what_the_frame_did() {
+    let mut chip8 = Chip8::new()
+        .with_cycles_per_frame(2)
+        .load_rom_bytes(&[
+            0xF0, 0x29, // 200: i := hex v0
+            0xD0, 0x05, // 202: sprite v0 v0 5
+            0x60, 0x02, // 204: v0 := 2
+            0xF0, 0x18, // 206: buzzer := v0
+            0xF0, 0x0A, // 208: v0 := key
+            0x12, 0x0A, // 20A: jump 20A
+        ])
+        .unwrap();
+    let frame = chip8.run_frame().unwrap();
+    assert!(frame.display_changed);
+    assert!(!frame.sound_on);
+    // Still dirty, but nothing new was drawn
+    let frame = chip8.run_frame().unwrap();
+    assert!(!frame.display_changed);
+    assert!(frame.sound_on);
+    assert!(chip8.take_dirty_region().is_some());
+    let frame = chip8.run_frame().unwrap();
+    assert!(frame.waiting_for_key);
+    assert!(!frame.sound_on);
+
+    chip8.key_down(3);
+    chip8.run_frame().unwrap();
+    chip8.key_up(3);
+    let frame = chip8.run_frame().unwrap();
+    assert!(!frame.waiting_for_key);
+    assert_eq!(chip8.registers()[0], 3);
+
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::SuperChip)
+        .load_rom_bytes(&[0x00, 0xFD])
+        .unwrap();
+    assert!(chip8.run_frame().unwrap().halted);
+}
+
+#[test]
+fn vip_frames_run_until_their_cycles_run_out() {
+    let mut chip8 = Chip8::new()
+        .with_timing(Timing::CosmacVip)
+        .with_cycles_per_frame(1)
+        .load_rom_bytes(&[0x70, 0x01, 0x12, 0x00])
+        .unwrap();
+    chip8.run_frame().unwrap();
+    assert_eq!(
+        chip8.registers()[0] as u32,
+        VIP_CYCLES_PER_FRAME.div_ceil(33)
+    );
+}
+
+#[test]
+fn errors_stop_the_frame() {
+    let mut chip8 = Chip8::new().load_rom_bytes(&[0xFF, 0xFF]).unwrap();
+    assert!(chip8.run_frame().is_err());
+}
+
+#[test]
+fn the_clock_keeps_a_steady_timeline() {
+    let start = Instant::now();
+    let mut clock = FrameClock::new();
+    clock.reset_at(start);
+
+    // The first frame is due right away
+    assert_eq!(clock.due_frames_at(start), 1);
+    assert_eq!(clock.due_frames_at(start + PERIOD / 2), 0);
+    assert_eq!(clock.due_frames_at(start + PERIOD), 1);
+    // Waking up late doesn't push the next frames back
+    assert_eq!(clock.due_frames_at(start + PERIOD * 2 + PERIOD * 9 / 10), 1);
+    assert_eq!(clock.due_frames_at(start + PERIOD * 3), 1);
+    // Late frames are caught up
+    assert_eq!(clock.due_frames_at(start + PERIOD * 6), 3);
+    assert_eq!(clock.due_frames_at(start + PERIOD * 6), 0);
+}
+
+#[test]
+fn long_backlogs_are_dropped() {
+    let start = Instant::now();
+    let mut clock = FrameClock::new().with_max_catch_up(4);
+    clock.reset_at(start);
+    assert_eq!(clock.due_frames_at(start), 1);
+
+    let late = start + PERIOD * 100;
+    assert_eq!(clock.due_frames_at(late), 4);
+    // The timeline starts over from there
+    assert_eq!(clock.due_frames_at(late + PERIOD / 2), 0);
+    assert_eq!(clock.due_frames_at(late + PERIOD), 1);
+
+    let mut clock = FrameClock::new().with_rate(30);
+    clock.reset_at(start);
+    clock.due_frames_at(start);
+    assert_eq!(clock.due_frames_at(start + PERIOD), 0);
+    assert_eq!(clock.due_frames_at(start + PERIOD * 21 / 10), 1);
+}
+
+#[test]
+fn the_clock_runs_frames_when_they_are_due() {
+    let mut chip8 = Chip8::new()
+        .with_cycles_per_frame(1)
+        .load_rom_bytes(&[0x70, 0x01, 0x12, 0x00])
+        .unwrap();
+    let mut clock = FrameClock::new();
+    assert!(clock.run(&mut chip8).unwrap().is_some());
+    assert_eq!(chip8.registers()[0], 1);
+    // The next one is a whole frame away
+    assert!(clock.until_next() > Duration::ZERO);
+    assert!(clock.run(&mut chip8).unwrap().is_none());
+}
+
+#[test]
+fn save_states_leave_the_frame_size_alone() {
+    let chip8 = Chip8::new().load_rom_bytes(&[0x12, 0x00]).unwrap();
+    let mut other = Chip8::new().with_cycles_per_frame(30);
+    other.load_state(&chip8.save_state()).unwrap();
+    assert_eq!(other.cycles_per_frame(), 30);
+}
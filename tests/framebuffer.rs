@@ -0,0 +1,60 @@
+use rust_8::{Chip8, Variant};
+
+#[test]
+fn framebuffer_shows_drawn_sprites() {
+    // v0 := 10; v1 := 5; i := font 0; draw it
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[0x60, 0x0A, 0x61, 0x05, 0xF0, 0x29, 0xD0, 0x15])
+        .unwrap();
+    chip8.run(4).unwrap();
+
+    let frame = chip8.framebuffer();
+    assert_eq!((frame.width(), frame.height()), (64, 32));
+    // Top row of the 0 glyph is 0xF0
+    assert_eq!(&frame.row(5)[10..14], &[1, 1, 1, 1]);
+    assert_eq!(frame.pixel(14, 5), 0);
+    assert_eq!(frame.rows().count(), 32);
+}
+
+#[test]
+fn dirty_region_covers_what_changed() {
+    // v0 := 10; v1 := 5; i := font 1; draw it; clear
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[
+            0x60, 0x0A, 0x61, 0x05, 0x62, 0x01, 0xF2, 0x29, 0xD0, 0x15, 0x00, 0xE0,
+        ])
+        .unwrap();
+
+    // A fresh machine needs a full redraw
+    let initial = chip8.take_dirty_region().unwrap();
+    assert_eq!(initial.bounds(), (0, 0, 64, 32));
+    assert!(chip8.take_dirty_region().is_none());
+
+    chip8.run(4).unwrap();
+    assert!(chip8.take_dirty_region().is_none());
+
+    // The 1 glyph is 0x20 0x60 0x20 0x20 0x70: columns 1 to 3 of the sprite
+    chip8.run(1).unwrap();
+    let region = chip8.take_dirty_region().unwrap();
+    assert_eq!(region.rows().collect::<Vec<_>>(), vec![5, 6, 7, 8, 9]);
+    assert_eq!(region.bounds(), (11, 5, 3, 5));
+    assert!(!region.is_row_dirty(4));
+
+    // The COSMAC VIP waits for vblank after drawing
+    chip8.tick_timers();
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.take_dirty_region().unwrap().bounds(), (0, 0, 64, 32));
+}
+
+#[test]
+fn hires_framebuffer_is_128x64() {
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::SuperChip)
+        .load_rom_bytes(&[0x00, 0xFF])
+        .unwrap();
+    chip8.run(1).unwrap();
+
+    let frame = chip8.framebuffer();
+    assert_eq!((frame.width(), frame.height()), (128, 64));
+    assert_eq!(chip8.dirty_region().unwrap().rows().count(), 64);
+}
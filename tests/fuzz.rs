@@ -0,0 +1,59 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rust_8::{Chip8, Chip8Error, Quirks, Variant};
+
+// Feeds ROMs made of random bytes through `run`. Errors are expected, panics are not.
+#[test]
+fn random_opcodes_never_panic() {
+    for seed in 0..64 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut rom = vec![0u8; 4096 - 0x200];
+        rng.fill(&mut rom[..]);
+
+        let quirks = match seed % 4 {
+            0 => Quirks::COSMAC_VIP,
+            1 => Quirks::CHIP_48,
+            2 => Quirks::SUPER_CHIP,
+            _ => Quirks::XO_CHIP,
+        };
+        let variant = match seed % 12 / 4 {
+            0 => Variant::Chip8,
+            1 => Variant::SuperChip,
+            _ => Variant::XoChip,
+        };
+        let mut chip8 = Chip8::new()
+            .with_variant(variant)
+            .with_quirks(quirks)
+            .load_rom_bytes(&rom)
+            .unwrap();
+
+        for _ in 0..10_000 {
+            let key = rng.random_range(0..16);
+            chip8.keyboard[key] = rng.random();
+
+            // A failed instruction has already been fetched, so the next run just moves on.
+            let _ = chip8.run(1);
+            chip8.tick_timers();
+        }
+    }
+}
+
+#[test]
+fn out_of_bounds_errors_report_the_first_address_past_the_end() {
+    let cases: [(&[u8], usize); 2] = [
+        // Starts inside memory and runs off the end
+        (&[0xAF, 0xFE, 0xF3, 0x55], 0x1000),
+        // Starts past the end
+        (&[0xAF, 0xFF, 0x60, 0x05, 0xF0, 0x1E, 0xF0, 0x55], 0x1004),
+    ];
+    for (rom, expected) in cases {
+        let mut chip8 = Chip8::new().load_rom_bytes(rom).unwrap();
+        let pc = 0x200 + rom.len() as u16 - 2;
+        match chip8.run(rom.len() / 2) {
+            Err(Chip8Error::MemoryOutOfBounds { pc: at, address }) => {
+                assert_eq!(at, pc);
+                assert_eq!(address, expected);
+            }
+            _ => panic!("expected MemoryOutOfBounds"),
+        }
+    }
+}
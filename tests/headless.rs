@@ -0,0 +1,110 @@
+use rust_8::{Chip8, PALETTE, render::encode_png};
+use std::process::{Command, Output};
+use std::{env, fs, process};
+
+fn rust_8(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust-8"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn temp_rom(name: &str, bytes: &[u8]) -> String {
+    let path = env::temp_dir().join(format!("rust-8-headless-{}-{}", process::id(), name));
+    fs::write(&path, bytes).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn prints_the_final_screen() {
+    let output = rust_8(&[
+        "run",
+        "--headless",
+        "--frames",
+        "30",
+        "--screen",
+        "-",
+        "test_roms/1-ibm-logo.ch8",
+    ]);
+    assert!(output.status.success());
+    let screen = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = screen.lines().collect();
+    assert_eq!(rows.len(), 32);
+    assert!(rows.iter().all(|row| row.len() == 64));
+    assert_eq!(
+        rows[8],
+        "............########.#########...#####.........#####............"
+    );
+}
+
+#[test]
+fn scripted_keys_reach_the_program() {
+    let rom = temp_rom(
+        "keys.ch8",
+        &[
+            0xF0, 0x0A, // 200: v0 := key
+            0xF0, 0x29, // 202: i := hex v0
+            0x61, 0x00, // 204: v1 := 0
+            0xD1, 0x15, // 206: sprite v1 v1 5
+            0x12, 0x08, // 208: jump 208
+        ],
+    );
+    let screen = |keys: &str| {
+        let output = rust_8(&[
+            "--headless",
+            "--frames",
+            "10",
+            "--keys",
+            keys,
+            "--screen",
+            "-",
+            &rom,
+        ]);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Nothing drawn until the key goes up
+    assert!(!screen("2:7:20").contains('#'));
+    let seven: Vec<String> = screen("# press 7\n2:7:3")
+        .lines()
+        .take(5)
+        .map(|row| row[..4].to_string())
+        .collect();
+    assert_eq!(seven, ["####", "...#", "..#.", ".#..", ".#.."]);
+    fs::remove_file(&rom).unwrap();
+}
+
+#[test]
+fn cpu_errors_exit_with_a_failure() {
+    let rom = temp_rom("bad.ch8", &[0xFF, 0xFF]);
+    let output = rust_8(&["run", "--headless", "--frames", "5", &rom]);
+    fs::remove_file(&rom).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let errors = String::from_utf8(output.stderr).unwrap();
+    assert!(errors.contains("unknown opcode 0xFFFF"), "{}", errors);
+
+    let output = rust_8(&["--headless", "test_roms/1-ibm-logo.ch8"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn png_screenshots() {
+    let chip8 = Chip8::new()
+        .load_rom(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_roms/1-ibm-logo.ch8"
+        ))
+        .unwrap();
+    let png = encode_png(chip8.framebuffer(), &PALETTE, 3);
+    assert_eq!(
+        png[..8],
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']
+    );
+    assert_eq!(&png[12..16], b"IHDR");
+    // 192x96, 8 bit RGB
+    assert_eq!(png[16..26], [0, 0, 0, 192, 0, 0, 0, 96, 8, 2]);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    // Every IEND chunk has the same checksum
+    assert_eq!(png[png.len() - 4..], [0xAE, 0x42, 0x60, 0x82]);
+}
@@ -0,0 +1,74 @@
+use rust_8::Chip8;
+
+// v3 := key, then spin
+const GET_KEY: [u8; 4] = [0xF3, 0x0A, 0x12, 0x02];
+
+fn waiting_for_key() -> Chip8 {
+    let mut chip8 = Chip8::new().load_rom_bytes(&GET_KEY).unwrap();
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.waiting_for_key(), Some(3));
+    chip8
+}
+
+#[test]
+fn get_key_completes_on_release() {
+    let mut chip8 = waiting_for_key();
+
+    chip8.key_down(0x7);
+    chip8.run(10).unwrap();
+    assert_eq!(chip8.waiting_for_key(), Some(3));
+    assert_eq!(chip8.program_counter(), 0x200);
+
+    chip8.key_up(0x7);
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.waiting_for_key(), None);
+    assert_eq!(chip8.registers()[3], 0x7);
+    assert_eq!(chip8.program_counter(), 0x202);
+}
+
+#[test]
+fn a_tap_between_instructions_still_counts() {
+    let mut chip8 = waiting_for_key();
+    chip8.key_down(0xA);
+    chip8.key_up(0xA);
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.registers()[3], 0xA);
+}
+
+#[test]
+fn a_key_held_before_the_wait_counts_when_released() {
+    let mut chip8 = Chip8::new().load_rom_bytes(&GET_KEY).unwrap();
+    chip8.key_down(0x2);
+    chip8.run(5).unwrap();
+    assert_eq!(chip8.waiting_for_key(), Some(3));
+
+    chip8.key_up(0x2);
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.registers()[3], 0x2);
+}
+
+#[test]
+fn writing_the_keyboard_directly_works_too() {
+    let mut chip8 = waiting_for_key();
+    chip8.keyboard[0xC] = true;
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.waiting_for_key(), Some(3));
+
+    chip8.keyboard[0xC] = false;
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.registers()[3], 0xC);
+}
+
+#[test]
+fn the_held_key_survives_a_save_state() {
+    let mut chip8 = waiting_for_key();
+    chip8.keyboard[0x4] = true;
+    chip8.run(1).unwrap();
+    let state = chip8.save_state();
+
+    let mut restored = Chip8::new();
+    restored.load_state(&state).unwrap();
+    restored.keyboard[0x4] = false;
+    restored.run(1).unwrap();
+    assert_eq!(restored.registers()[3], 0x4);
+}
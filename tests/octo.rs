@@ -0,0 +1,242 @@
+use rust_8::{
+    Chip8, Variant,
+    disasm::{Syntax, disassemble_variant},
+    octo::Compiler,
+};
+
+fn compile(source: &str) -> Vec<u8> {
+    Compiler::new().compile(source).unwrap()
+}
+
+fn error(source: &str) -> String {
+    Compiler::new().compile(source).unwrap_err().to_string()
+}
+
+// Runs until the program spins on a jump to itself
+fn run(source: &str) -> Chip8 {
+    let image = compile(source);
+    let mut chip8 = Chip8::new().with_seed(1).load_rom_bytes(&image).unwrap();
+    for _ in 0..10_000 {
+        let pc = chip8.program_counter();
+        if chip8.opcode_at(pc) == 0x1000 | pc {
+            return chip8;
+        }
+        chip8.step().unwrap();
+    }
+    panic!("the program never finished");
+}
+
+#[test]
+fn every_opcode_survives_a_round_trip() {
+    // Octo listings of every instruction compile back to the same instruction
+    let xo = Compiler::new().with_variant(Variant::XoChip);
+    for opcode in 0..=0xFFFFu16 {
+        let [high, low] = opcode.to_be_bytes();
+        let bytes = [high, low, 0x12, 0x34];
+        let line = &disassemble_variant(&bytes, 0x200, Variant::XoChip)[0];
+        if !line.is_code() {
+            continue;
+        }
+
+        let source = line.mnemonic(Syntax::Octo);
+        let image = xo
+            .compile(&format!(": main {}", source))
+            .unwrap_or_else(|e| panic!("{:04X} {}: {}", opcode, source, e));
+        let again = &disassemble_variant(&image, 0x200, Variant::XoChip)[0];
+        assert_eq!(again.mnemonic(Syntax::Octo), source, "{:04X}", opcode);
+    }
+}
+
+#[test]
+fn execution_starts_at_main() {
+    assert_eq!(compile(": main clear"), [0x00, 0xE0]);
+    // Anything before main gets jumped over
+    assert_eq!(
+        compile(": sub return : main sub"),
+        [0x12, 0x04, 0x00, 0xEE, 0x22, 0x02]
+    );
+    assert_eq!(
+        error(": sub return"),
+        "<source>:1: the program has no ': main' label"
+    );
+}
+
+#[test]
+fn labels_can_be_used_before_they_are_defined() {
+    let image = compile(
+        ": main
+             i := ball
+             routine
+             jump main
+         : routine
+             v0 += 1
+         ;
+         : ball 0x3C 0x42",
+    );
+    assert_eq!(
+        image,
+        [
+            0xA2, 0x0A, 0x22, 0x06, 0x12, 0x00, 0x70, 0x01, 0x00, 0xEE, 0x3C, 0x42
+        ]
+    );
+}
+
+#[test]
+fn loops_and_branches() {
+    let chip8 = run(": main
+             v0 := 0
+             v1 := 0
+             loop
+                 v0 += 1
+                 if v0 > 4 begin
+                     v1 += 10
+                 else
+                     v1 += 1
+                 end
+                 while v0 != 8
+             again
+             v2 := 0x42
+         : done jump done");
+    assert_eq!(chip8.registers()[0..3], [8, 44, 0x42]);
+}
+
+#[test]
+fn comparisons_match_their_meaning() {
+    for operator in ["==", "!=", "<", ">", "<=", ">="] {
+        for (a, b) in [(0, 0), (1, 2), (2, 1), (0, 255), (255, 0), (128, 128)] {
+            let chip8 = run(&format!(
+                ": main
+                     v0 := {a}
+                     v1 := {b}
+                     if v0 {operator} v1 then v2 := 1
+                     if v0 {operator} {b} then v3 := 1
+                 : done jump done"
+            ));
+            let expected = u8::from(match operator {
+                "==" => a == b,
+                "!=" => a != b,
+                "<" => a < b,
+                ">" => a > b,
+                "<=" => a <= b,
+                _ => a >= b,
+            });
+            assert_eq!(
+                chip8.registers()[2..4],
+                [expected, expected],
+                "{} {} {}",
+                a,
+                operator,
+                b
+            );
+        }
+    }
+}
+
+#[test]
+fn constants_aliases_and_calc() {
+    let image = compile(
+        ":const SPEED 3
+         :alias x v5
+         :calc RIGHT_TO_LEFT { 2 * 3 + 4 }
+         :calc GROUPED { ( 2 * 3 ) + 4 }
+         : main
+             x := SPEED
+             x += { SPEED * 2 }
+             x -= 1
+             v0 := RIGHT_TO_LEFT
+             v1 := GROUPED",
+    );
+    assert_eq!(
+        image,
+        [0x65, 0x03, 0x75, 0x06, 0x75, 0xFF, 0x60, 0x0E, 0x61, 0x0A]
+    );
+}
+
+#[test]
+fn macros_substitute_their_arguments() {
+    let chip8 = run(":macro swap A B { vf := A A := B B := vf }
+         : main
+             v0 := 1
+             v1 := 2
+             swap v0 v1
+         : done jump done");
+    assert_eq!(chip8.registers()[0..2], [2, 1]);
+    assert!(error(":macro forever { forever } : main forever").contains("recursive"));
+}
+
+#[test]
+fn org_unpack_and_next() {
+    let image = compile(
+        ": main
+             :unpack 0xA table
+             :next counter v2 := 0
+         :org 0x20A
+         : table 1 2 3",
+    );
+    assert_eq!(
+        image,
+        [0x60, 0xA2, 0x61, 0x0A, 0x62, 0x00, 0, 0, 0, 0, 1, 2, 3]
+    );
+    // An :org to the start places main there, rather than writing over the jump to it
+    assert_eq!(
+        compile(":org 0x200 : main v0 := 5 : done jump done"),
+        [0x60, 0x05, 0x12, 0x02]
+    );
+    assert_eq!(
+        compile(":org 0x200 v0 := 5 : main jump main"),
+        [0x12, 0x04, 0x60, 0x05, 0x12, 0x04]
+    );
+    assert!(error(": f v0 := 1 :org 0x200 : main jump main").contains("the jump to main is there"));
+    assert!(error(": f v0 := 1 :org 0x201 : main jump main").contains("the jump to main is there"));
+    // :next points at the operand of the instruction that follows
+    assert_eq!(
+        compile(": main :next spot v0 := 7 i := spot"),
+        [0x60, 0x07, 0xA2, 0x01]
+    );
+}
+
+#[test]
+fn long_index_on_xo_chip() {
+    let xo = Compiler::new().with_variant(Variant::XoChip);
+    let image = xo
+        .compile(": main i := long data exit :org 0x1000 : data 0xFF")
+        .unwrap();
+    assert_eq!(image[..6], [0xF0, 0x00, 0x10, 0x00, 0x00, 0xFD]);
+    assert_eq!(image.len(), 0x1000 - 0x200 + 1);
+}
+
+#[test]
+fn instructions_are_checked_against_the_variant() {
+    assert_eq!(
+        error(": main\n  hires"),
+        "<source>:2: hires isn't part of the CHIP-8 instruction set"
+    );
+    let schip = Compiler::new().with_variant(Variant::SuperChip);
+    assert_eq!(schip.compile(": main hires").unwrap(), [0x00, 0xFF]);
+    assert!(schip.compile(": main plane 2").is_err());
+}
+
+#[test]
+fn errors_have_line_numbers() {
+    assert_eq!(
+        error(": main\n\n  jump nowhere"),
+        "<source>:3: undefined name 'nowhere'"
+    );
+    assert_eq!(
+        error(": main v0 := 256"),
+        "<source>:1: 256 doesn't fit in a byte"
+    );
+    assert_eq!(
+        error(": main\n: main"),
+        "<source>:2: 'main' is already defined"
+    );
+    assert_eq!(
+        error(": main\nloop\n  v0 += 1"),
+        "<source>:2: loop without again"
+    );
+    assert_eq!(error(": main again"), "<source>:1: again without loop");
+    assert_eq!(
+        error(": main v0 |= 3"),
+        "<source>:1: |= needs a register, found '3'"
+    );
+}
@@ -0,0 +1,66 @@
+use rust_8::{Chip8, Quirks, Variant};
+
+// Quirk fields in declaration order
+fn table(q: Quirks) -> [bool; 6] {
+    [
+        q.shift_uses_vy,
+        q.memory_increments_i,
+        q.jump_uses_vx,
+        q.vf_reset,
+        q.clip_sprites,
+        q.display_wait,
+    ]
+}
+
+#[test]
+fn presets_have_the_documented_quirks() {
+    // What the emulator did before presets existed
+    assert_eq!(
+        table(Quirks::LEGACY),
+        [true, false, false, false, true, false]
+    );
+    assert_eq!(
+        table(Quirks::COSMAC_VIP),
+        [true, true, false, true, true, true]
+    );
+    assert_eq!(
+        table(Quirks::CHIP_48),
+        [false, false, true, false, true, false]
+    );
+    assert_eq!(Quirks::SUPER_CHIP, Quirks::CHIP_48);
+    assert_eq!(
+        table(Quirks::XO_CHIP),
+        [true, true, false, false, false, false]
+    );
+}
+
+#[test]
+fn presets_are_found_by_name() {
+    for name in Quirks::PRESET_NAMES {
+        assert!(Quirks::preset(name).is_some(), "{}", name);
+    }
+    assert_eq!(Quirks::preset("legacy"), Some(Quirks::LEGACY));
+    // chip8 means what --variant chip8 runs with
+    assert_eq!(
+        Quirks::preset("chip8"),
+        Some(Variant::Chip8.default_quirks())
+    );
+    assert_eq!(Quirks::preset("VIP"), Some(Quirks::COSMAC_VIP));
+    assert_eq!(Quirks::preset("cosmac"), Some(Quirks::COSMAC_VIP));
+    assert_eq!(Quirks::preset("chip48"), Some(Quirks::CHIP_48));
+    assert_eq!(Quirks::preset("SuperChip"), Some(Quirks::SUPER_CHIP));
+    assert_eq!(Quirks::preset("xo"), Some(Quirks::XO_CHIP));
+    assert_eq!(Quirks::preset("chip-9"), None);
+}
+
+#[test]
+fn machines_start_with_the_default_quirks() {
+    // VIP is opt-in, ROMs keep running the way they used to
+    assert_eq!(Quirks::default(), Quirks::LEGACY);
+    assert_eq!(Variant::Chip8.default_quirks(), Quirks::LEGACY);
+    assert_eq!(Chip8::new().quirks(), Quirks::default());
+    assert_eq!(
+        Chip8::new().with_quirks(Quirks::CHIP_48).quirks(),
+        Quirks::CHIP_48
+    );
+}
@@ -0,0 +1,135 @@
+use rust_8::{
+    Chip8, PALETTE, Variant,
+    render::{Renderer, TerminalRenderer, Viewport, parse_color},
+};
+
+// The "0" glyph at the top left corner: F0 90 90 90 F0
+fn zero() -> Chip8 {
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[
+            0xF0, 0x29, // 200: i := hex v0
+            0xD0, 0x05, // 202: sprite v0 v0 5
+            0x61, 0x04, // 204: v1 := 4
+            0xF1, 0x1E, // 206: i += v1
+            0xD0, 0x01, // 208: sprite v0 v0 1, erasing the top row
+        ])
+        .unwrap();
+    chip8.run(2).unwrap();
+    chip8
+}
+
+// The rows the renderer moved to and the text it printed on each, escape sequences left out.
+fn printed(output: &[u8]) -> Vec<(usize, String)> {
+    let output = String::from_utf8(output.to_vec()).unwrap();
+    let mut rows: Vec<(usize, String)> = Vec::new();
+    for sequence in output.split('\x1B').skip(1) {
+        let end = sequence.find(|c: char| c.is_ascii_alphabetic()).unwrap();
+        let (command, text) = (&sequence[..=end], &sequence[end + 1..]);
+        if command.ends_with('H') {
+            let row = command[1..].split(';').next().unwrap();
+            rows.push((row.parse::<usize>().unwrap() - 1, String::new()));
+        }
+        if let Some((_, line)) = rows.last_mut() {
+            line.push_str(text);
+        }
+    }
+    rows
+}
+
+#[test]
+fn the_terminal_draws_every_row_of_the_frame() {
+    let chip8 = zero();
+    let mut renderer = TerminalRenderer::with_output(Vec::new());
+    renderer.render(chip8.framebuffer()).unwrap();
+
+    let rows = printed(renderer.output());
+    // 32 rows, then the cursor parks below them
+    assert_eq!(rows.len(), 33);
+    assert_eq!(rows[32], (32, String::new()));
+    let glyph = ["████████", "██    ██", "██    ██", "██    ██", "████████"];
+    for (y, (row, line)) in rows[..32].iter().enumerate() {
+        assert_eq!(*row, y);
+        // Two characters per pixel
+        assert_eq!(line.chars().count(), 128);
+        let expected = glyph.get(y).copied().unwrap_or("");
+        assert_eq!(line.trim_end(), expected);
+    }
+}
+
+#[test]
+fn only_the_dirty_rows_are_drawn_again() {
+    let mut chip8 = zero();
+    let mut renderer = TerminalRenderer::with_output(Vec::new());
+    renderer.render(chip8.framebuffer()).unwrap();
+    chip8.take_dirty_region();
+
+    chip8.run(3).unwrap();
+    let region = chip8.take_dirty_region().unwrap();
+    let drawn = renderer.output().len();
+    renderer.render_region(chip8.framebuffer(), region).unwrap();
+
+    let rows = printed(&renderer.output()[drawn..]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].0, 0);
+    assert_eq!(rows[0].1.trim_end(), "");
+    assert_eq!(rows[1].0, 32);
+}
+
+#[test]
+fn xo_chip_planes_are_drawn_in_their_palette_colors() {
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::XoChip)
+        .load_rom_bytes(&[
+            0xF2, 0x01, // 200: plane 2
+            0xF0, 0x29, // 202: i := hex v0
+            0xD0, 0x01, // 204: sprite v0 v0 1
+        ])
+        .unwrap();
+    chip8.run(3).unwrap();
+    let mut renderer = TerminalRenderer::with_output(Vec::new());
+    renderer.render(chip8.framebuffer()).unwrap();
+
+    let output = String::from_utf8(renderer.output().clone()).unwrap();
+    let (r, g, b) = PALETTE[2];
+    let color = format!("\x1B[38;2;{};{};{}m██", r, g, b);
+    assert_eq!(output.matches(&color).count(), 4);
+    assert_eq!(printed(renderer.output())[0].1.trim_end(), "████████");
+}
+
+#[test]
+fn the_screen_is_scaled_by_whole_factors_and_centered() {
+    let fit = |resolution, window| {
+        let viewport = Viewport::fit(resolution, window);
+        (viewport.scale, viewport.x, viewport.y)
+    };
+    assert_eq!(fit((64, 32), (640, 320)), (10, 0, 0));
+    // Limited by the height, the bars go left and right
+    assert_eq!(fit((64, 32), (1000, 320)), (10, 180, 0));
+    // Limited by the width, the bars go above and below
+    assert_eq!(fit((64, 32), (640, 500)), (10, 0, 90));
+    // Never a fraction, the rest is split between both sides
+    assert_eq!(fit((64, 32), (703, 351)), (10, 31, 15));
+    assert_eq!(fit((128, 64), (640, 320)), (5, 0, 0));
+    // Too small to fit at all, the screen gets cut off
+    assert_eq!(
+        Viewport::fit((128, 64), (100, 50)),
+        Viewport {
+            scale: 1,
+            x: 0,
+            y: 0,
+            width: 128,
+            height: 64,
+        }
+    );
+}
+
+#[test]
+fn colors_are_six_hex_digits() {
+    assert_eq!(parse_color("FF8000"), Some((0xFF, 0x80, 0x00)));
+    assert_eq!(parse_color("#1a2B3c"), Some((0x1A, 0x2B, 0x3C)));
+    for bad in [
+        "", "#", "FFF", "FF80000", "#FF800", "GG0000", "+FFFFF", "FF 000", "##FF8000", "ÿÿÿ",
+    ] {
+        assert_eq!(parse_color(bad), None, "{}", bad);
+    }
+}
@@ -0,0 +1,111 @@
+use rust_8::{Chip8, Rewind, Variant};
+
+// v0 += 1 every frame, and a sprite drawn at x v0
+fn counter() -> Chip8 {
+    Chip8::new()
+        .with_cycles_per_frame(4)
+        .load_rom_bytes(&[
+            0x70, 0x01, // 200: v0 += 1
+            0xF0, 0x29, // 202: i := hex v0
+            0xD0, 0x15, // 204: sprite v0 v1 5
+            0x12, 0x00, // 206: jump 200
+        ])
+        .unwrap()
+}
+
+#[test]
+fn stepping_back_goes_through_the_snapshots_newest_first() {
+    let mut chip8 = counter();
+    let mut rewind = Rewind::new().with_interval(1);
+    let mut seen = Vec::new();
+    for _ in 0..10 {
+        chip8.run_frame().unwrap();
+        rewind.record(&chip8);
+        seen.push((chip8.registers()[0], chip8.framebuffer().to_ascii()));
+    }
+    assert_eq!(rewind.len(), 10);
+
+    for (v0, screen) in seen.iter().rev() {
+        assert!(rewind.step_back(&mut chip8).unwrap());
+        assert_eq!(chip8.registers()[0], *v0);
+        assert_eq!(chip8.framebuffer().to_ascii(), *screen);
+    }
+    // The oldest snapshot stays
+    assert_eq!(rewind.len(), 1);
+    assert!(rewind.step_back(&mut chip8).unwrap());
+    assert_eq!(chip8.registers()[0], seen[0].0);
+
+    // And running on from there works as usual
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.registers()[0], seen[1].0);
+}
+
+#[test]
+fn snapshots_are_taken_every_interval_and_bounded() {
+    let mut chip8 = counter();
+    let mut rewind = Rewind::new().with_interval(10).with_capacity(3);
+    assert!(rewind.is_empty());
+    assert!(!rewind.step_back(&mut chip8).unwrap());
+
+    // Frames 1, 11 and 21
+    for _ in 0..25 {
+        chip8.run_frame().unwrap();
+        rewind.record(&chip8);
+    }
+    assert_eq!(rewind.len(), 3);
+    rewind.step_back(&mut chip8).unwrap();
+    assert_eq!(chip8.registers()[0], 21);
+
+    // Back at frame 21 the interval starts over: frames 31, 41 and 51 push out 1 and 11
+    for _ in 0..30 {
+        chip8.run_frame().unwrap();
+        rewind.record(&chip8);
+    }
+    assert_eq!(rewind.len(), 3);
+    let mut oldest = 0;
+    for _ in 0..5 {
+        rewind.step_back(&mut chip8).unwrap();
+        oldest = chip8.registers()[0];
+    }
+    assert_eq!(oldest, 31);
+
+    rewind.clear();
+    assert!(rewind.is_empty());
+    assert_eq!(rewind.size(), 0);
+}
+
+#[test]
+fn older_snapshots_only_keep_what_changed() {
+    let mut chip8 = counter();
+    let state = chip8.save_state().len();
+    let mut rewind = Rewind::new().with_interval(1);
+    for _ in 0..100 {
+        chip8.run_frame().unwrap();
+        rewind.record(&chip8);
+    }
+    // One whole state, then a few bytes of registers, timers, checksum and sprite rows per frame
+    assert!(rewind.size() < state + 99 * 200, "{} bytes", rewind.size());
+}
+
+#[test]
+fn snapshots_of_different_sizes_still_step_back() {
+    let mut chip8 = counter();
+    chip8.run_frame().unwrap();
+    let mut xo = Chip8::new()
+        .with_variant(Variant::XoChip)
+        .load_rom_bytes(&[0x12, 0x00])
+        .unwrap();
+    xo.run_frame().unwrap();
+
+    let mut rewind = Rewind::new();
+    rewind.push(chip8.save_state());
+    rewind.push(xo.save_state());
+    rewind.push(chip8.save_state());
+
+    let mut other = Chip8::new();
+    for variant in [Variant::Chip8, Variant::XoChip, Variant::Chip8] {
+        rewind.step_back(&mut other).unwrap();
+        assert_eq!(other.variant(), variant);
+    }
+    assert_eq!(other.registers()[0], 1);
+}
@@ -0,0 +1,51 @@
+use rust_8::{Chip8, Chip8Error};
+
+#[test]
+fn rom_filling_memory_loads() {
+    let rom = vec![0x12; 4096 - 0x200];
+    assert!(Chip8::new().load_rom_bytes(&rom).is_ok());
+}
+
+#[test]
+fn oversized_rom_is_rejected() {
+    let rom = vec![0x12; 4096 - 0x200 + 1];
+    match Chip8::new().load_rom_bytes(&rom) {
+        Err(Chip8Error::RomTooLarge { size, max }) => {
+            assert_eq!(size, 3585);
+            assert_eq!(max, 3584);
+        }
+        _ => panic!("expected RomTooLarge"),
+    }
+}
+
+#[test]
+fn entry_point_moves_the_load_address() {
+    // 0x600: 6A 2A (v[A] := 0x2A), then jump to itself
+    let mut chip8 = Chip8::new()
+        .with_entry_point(0x600)
+        .load_rom_bytes(&[0x6A, 0x2A, 0x16, 0x02])
+        .unwrap();
+    chip8.run(2).unwrap();
+
+    // With a 0x600 entry point, less memory is left for the ROM.
+    let rom = vec![0; 4096 - 0x600 + 1];
+    assert!(matches!(
+        chip8.reload_rom(&rom),
+        Err(Chip8Error::RomTooLarge { max: 2560, .. })
+    ));
+}
+
+#[test]
+fn reload_rom_resets_the_machine() {
+    // 2202: call 0x202, which calls itself until the stack overflows
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[0x00, 0xE0, 0x22, 0x02])
+        .unwrap();
+    assert!(matches!(
+        chip8.run(100),
+        Err(Chip8Error::StackOverflow { pc: 0x202 })
+    ));
+
+    chip8.reload_rom(&[0x00, 0xE0, 0x12, 0x00]).unwrap();
+    assert!(chip8.run(100).is_ok());
+}
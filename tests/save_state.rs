@@ -0,0 +1,80 @@
+use rust_8::{Chip8, Chip8Error, Quirks, Variant};
+
+// Draws random digits at random places forever, so the RNG state matters.
+const RANDOM_SPRITES: [u8; 12] = [
+    0xC0, 0x3F, // v0 := random 0x3F
+    0xC1, 0x1F, // v1 := random 0x1F
+    0xC2, 0x0F, // v2 := random 0x0F
+    0xF2, 0x29, // i := hex v2
+    0xD0, 0x15, // sprite v0 v1 5
+    0x12, 0x00, // jump 0x200
+];
+
+fn screen(chip8: &Chip8) -> Vec<Vec<u8>> {
+    chip8.framebuffer().rows().map(|row| row.to_vec()).collect()
+}
+
+fn random_sprites() -> Chip8 {
+    Chip8::new()
+        .with_quirks(Quirks::CHIP_48)
+        .load_rom_bytes(&RANDOM_SPRITES)
+        .unwrap()
+}
+
+#[test]
+fn restored_machine_continues_identically() {
+    let mut chip8 = random_sprites();
+    chip8.run(500).unwrap();
+    let state = chip8.save_state();
+
+    chip8.run(1000).unwrap();
+    let expected = screen(&chip8);
+
+    // Restore into a machine that has been doing something else entirely
+    let mut restored = Chip8::new()
+        .with_variant(Variant::XoChip)
+        .load_rom_bytes(&[0x00, 0xFF, 0x12, 0x02])
+        .unwrap();
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.quirks(), Quirks::CHIP_48);
+    assert_eq!(restored.variant(), Variant::Chip8);
+
+    restored.run(1000).unwrap();
+    assert_eq!(screen(&restored), expected);
+    assert_eq!(restored.save_state(), chip8.save_state());
+}
+
+#[test]
+fn corrupted_states_are_rejected() {
+    let mut chip8 = random_sprites();
+    chip8.run(100).unwrap();
+    let state = chip8.save_state();
+    let before = chip8.save_state();
+
+    let mut flipped = state.clone();
+    flipped[200] ^= 1;
+    assert!(matches!(
+        chip8.load_state(&flipped),
+        Err(Chip8Error::InvalidSaveState("checksum mismatch"))
+    ));
+
+    assert!(matches!(
+        chip8.load_state(&state[..state.len() - 1]),
+        Err(Chip8Error::InvalidSaveState("truncated"))
+    ));
+
+    let mut newer = state.clone();
+    newer[4] = 99;
+    assert!(matches!(
+        chip8.load_state(&newer),
+        Err(Chip8Error::InvalidSaveState("unsupported version"))
+    ));
+
+    assert!(matches!(
+        chip8.load_state(b"not a state at all"),
+        Err(Chip8Error::InvalidSaveState("not a save state"))
+    ));
+
+    // Failed loads leave the machine alone
+    assert_eq!(chip8.save_state(), before);
+}
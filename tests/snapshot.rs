@@ -0,0 +1,101 @@
+use rust_8::{Chip8, Variant, assert_screen_eq, snapshot::Screen};
+
+// Draws the font's 0 at the top left corner, then spins
+fn zero() -> Chip8 {
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[
+            0x60, 0x00, // 200: v0 := 0
+            0xF0, 0x29, // 202: i := hex v0
+            0xD0, 0x05, // 204: sprite v0 v0 5
+            0x12, 0x06, // 206: jump 206
+        ])
+        .unwrap();
+    chip8.run(3).unwrap();
+    chip8
+}
+
+#[test]
+fn ascii_round_trip() {
+    let chip8 = zero();
+    let text = chip8.framebuffer().to_ascii();
+    assert_eq!(text.lines().count(), 32);
+    assert!(text.starts_with("####."));
+
+    let screen = Screen::from_ascii(&text).unwrap();
+    assert_eq!((screen.width(), screen.height()), (64, 32));
+    assert_eq!(screen, chip8.framebuffer().to_screen());
+    assert_eq!(screen.to_ascii(), text);
+}
+
+#[test]
+fn regions_and_indented_art() {
+    assert_screen_eq!(
+        zero().framebuffer().to_screen().region(0, 0, 5, 5),
+        "
+        ####.
+        #..#.
+        #..#.
+        #..#.
+        ####.
+        "
+    );
+    // Cut to fit at the edges
+    let corner = zero().framebuffer().to_screen().region(62, 30, 8, 8);
+    assert_eq!((corner.width(), corner.height()), (2, 2));
+}
+
+#[test]
+fn xo_chip_colors() {
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::XoChip)
+        .load_rom_bytes(&[
+            0xF3, 0x01, // 200: plane 3
+            0xA2, 0x0A, // 202: i := 0x20A
+            0xD0, 0x01, // 204: sprite v0 v0 1
+            0x12, 0x06, // 206: jump 206
+            0x00, 0x00, //
+            0xC0, 0x80, // 20A: one row on plane 1, one on plane 2
+        ])
+        .unwrap();
+    chip8.run(3).unwrap();
+    assert_screen_eq!(chip8.framebuffer().to_screen().region(0, 0, 3, 1), "3#.");
+    assert_eq!(Screen::from_ascii(".#23").unwrap().to_ascii(), ".#23\n");
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        Screen::from_ascii("..\n...").unwrap_err(),
+        "row 1 is 3 pixels wide, expected 2"
+    );
+    assert_eq!(
+        Screen::from_ascii(".x").unwrap_err(),
+        "unexpected 'x' at x 1 y 0"
+    );
+}
+
+#[test]
+fn diffs_mark_every_mismatch() {
+    let actual = Screen::from_ascii("#..\n.#.").unwrap();
+    let expected = Screen::from_ascii("#.#\n...").unwrap();
+    assert_eq!(actual.diff(&actual), None);
+    assert_eq!(
+        actual.diff(&expected).unwrap(),
+        "2 pixels differ, the first one at x 2 y 0\n\
+         expected, ! where the screen differs:\n#.!\n.!.\n\
+         actual:\n#..\n.#.\n"
+    );
+    assert!(
+        actual
+            .diff(&Screen::from_ascii("#").unwrap())
+            .unwrap()
+            .starts_with("the screen is 3x2, expected 1x1")
+    );
+}
+
+#[test]
+#[should_panic(expected = "screens don't match: after one sprite\n14 pixels differ")]
+fn the_macro_panics_with_the_diff() {
+    let blank = ".".repeat(64) + "\n";
+    assert_screen_eq!(zero(), blank.repeat(32), "after one sprite");
+}
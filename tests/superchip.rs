@@ -0,0 +1,58 @@
+use rust_8::{Chip8, Chip8Error, Variant};
+
+#[test]
+fn superchip_opcodes_are_unknown_on_chip8() {
+    let mut chip8 = Chip8::new().load_rom_bytes(&[0x00, 0xFF]).unwrap();
+    assert!(matches!(
+        chip8.run(1),
+        Err(Chip8Error::UnknownOpcode {
+            pc: 0x200,
+            opcode: 0x00FF
+        })
+    ));
+}
+
+#[test]
+fn resolution_switches() {
+    // hires, lores, hires, then spin
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::SuperChip)
+        .load_rom_bytes(&[0x00, 0xFF, 0x00, 0xFE, 0x00, 0xFF, 0x12, 0x06])
+        .unwrap();
+    assert_eq!((chip8.display_width(), chip8.display_height()), (64, 32));
+
+    chip8.run(1).unwrap();
+    assert_eq!((chip8.display_width(), chip8.display_height()), (128, 64));
+
+    chip8.run(1).unwrap();
+    assert_eq!((chip8.display_width(), chip8.display_height()), (64, 32));
+
+    chip8.run(1).unwrap();
+    assert_eq!((chip8.display_width(), chip8.display_height()), (128, 64));
+}
+
+#[test]
+fn exit_halts_the_machine() {
+    // exit, followed by an invalid opcode that must never run
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::SuperChip)
+        .load_rom_bytes(&[0x00, 0xFD, 0xFF, 0xFF])
+        .unwrap();
+    chip8.run(10).unwrap();
+    assert!(chip8.is_halted());
+    chip8.run(10).unwrap();
+}
+
+#[test]
+fn big_sprites_and_scrolling_stay_in_bounds() {
+    // hires; v0 := 120; v1 := 60; i := 0x0A0; draw 16x16 at the corner; scroll every way
+    let rom = [
+        0x00, 0xFF, 0x60, 0x78, 0x61, 0x3C, 0xA0, 0xA0, 0xD0, 0x10, 0x00, 0xCF, 0x00, 0xFB, 0x00,
+        0xFC, 0x00, 0xFE, 0xD0, 0x10, 0x00, 0xC3, 0x00, 0xFB, 0x00, 0xFC,
+    ];
+    let mut chip8 = Chip8::new()
+        .with_variant(Variant::SuperChip)
+        .load_rom_bytes(&rom)
+        .unwrap();
+    chip8.run(13).unwrap();
+}
@@ -0,0 +1,106 @@
+use rust_8::{Chip8, Quirks, Timing, VIP_CYCLES_PER_FRAME};
+
+fn vip(rom: &[u8]) -> Chip8 {
+    Chip8::new()
+        .with_timing(Timing::CosmacVip)
+        .load_rom_bytes(rom)
+        .unwrap()
+}
+
+// Cycles spent by the first instruction of rom
+fn cost(rom: &[u8]) -> u32 {
+    let mut chip8 = vip(rom);
+    chip8.step().unwrap();
+    chip8.frame_cycles()
+}
+
+#[test]
+fn instructions_cost_their_vip_cycles() {
+    assert_eq!(cost(&[0x60, 0x01]), 6); // v0 := 1
+    assert_eq!(cost(&[0x70, 0x01]), 10); // v0 += 1
+    assert_eq!(cost(&[0x81, 0x24]), 44); // v1 += v2
+    // Skipping takes a little longer
+    assert_eq!(cost(&[0x30, 0x00]), cost(&[0x30, 0x01]) + 2);
+    // Saving more registers takes longer
+    assert!(cost(&[0xF5, 0x55]) > cost(&[0xF0, 0x55]));
+    // So do bigger digits: v0 := n, bcd v0
+    let bcd = |n: u8| {
+        let mut chip8 = vip(&[0x60, n, 0xF0, 0x33]);
+        chip8.run(2).unwrap();
+        chip8.frame_cycles() - 6
+    };
+    assert!(bcd(0) < bcd(9));
+    assert!(bcd(9) < bcd(199));
+}
+
+#[test]
+fn sprites_cost_more_off_byte_boundaries() {
+    // v0 := x, sprite v0 v0 5
+    let draw = |x: u8| {
+        let mut chip8 = vip(&[0x60, x, 0xD0, 0x05]);
+        chip8.run(2).unwrap();
+        chip8.frame_cycles()
+    };
+    assert!(draw(8) < draw(9));
+    assert!(draw(9) < draw(15));
+    assert_eq!(draw(0), draw(16));
+}
+
+#[test]
+fn frames_end_when_their_cycles_run_out() {
+    // loop: v0 += 1, jump loop
+    let mut chip8 = vip(&[0x70, 0x01, 0x12, 0x00]);
+    chip8.run(usize::MAX).unwrap();
+    assert!(chip8.is_waiting_for_vblank());
+    assert!(chip8.frame_cycles() >= VIP_CYCLES_PER_FRAME);
+    // 33 cycles per pass
+    assert_eq!(
+        chip8.registers()[0] as u32,
+        VIP_CYCLES_PER_FRAME.div_ceil(33)
+    );
+    assert!(!chip8.step().unwrap());
+
+    // What went past the end of the frame comes out of the next one
+    let over = chip8.frame_cycles() - VIP_CYCLES_PER_FRAME;
+    chip8.tick_timers();
+    assert_eq!(chip8.frame_cycles(), over);
+    chip8.run(usize::MAX).unwrap();
+    assert!(chip8.frame_cycles() >= VIP_CYCLES_PER_FRAME);
+}
+
+#[test]
+fn sprites_wait_for_vblank_whatever_the_quirks() {
+    let mut chip8 = vip(&[0xD0, 0x01, 0x60, 0x01, 0x12, 0x04]).with_quirks(Quirks::CHIP_48);
+    chip8.run(usize::MAX).unwrap();
+    assert_eq!(chip8.registers()[0], 0);
+    assert!(chip8.frame_cycles() < VIP_CYCLES_PER_FRAME);
+
+    // The rest of the frame is spent waiting
+    chip8.tick_timers();
+    assert_eq!(chip8.frame_cycles(), 0);
+    chip8.run(1).unwrap();
+    assert_eq!(chip8.registers()[0], 1);
+}
+
+#[test]
+fn fixed_timing_counts_nothing() {
+    let mut chip8 = Chip8::new()
+        .load_rom_bytes(&[0x70, 0x01, 0x12, 0x00])
+        .unwrap();
+    assert_eq!(chip8.timing(), Timing::Fixed);
+    chip8.run(10_000).unwrap();
+    assert_eq!(chip8.frame_cycles(), 0);
+    assert!(!chip8.is_waiting_for_vblank());
+}
+
+#[test]
+fn save_states_keep_the_timing() {
+    let mut chip8 = vip(&[0x70, 0x01, 0x12, 0x00]);
+    chip8.run(5).unwrap();
+    let state = chip8.save_state();
+
+    let mut other = Chip8::new();
+    other.load_state(&state).unwrap();
+    assert_eq!(other.timing(), Timing::CosmacVip);
+    assert_eq!(other.frame_cycles(), chip8.frame_cycles());
+}
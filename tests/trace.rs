@@ -0,0 +1,117 @@
+use rust_8::{Chip8, Tracer};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+// A writer the test can still read after handing it to the tracer
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Shared {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+}
+
+impl Write for Shared {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+const PROGRAM: [u8; 10] = [
+    0x60, 0x05, // 200: v0 := 5
+    0x70, 0xFF, // 202: v0 += 0xFF
+    0xA3, 0x00, // 204: i := 0x300
+    0x12, 0x06, // 206: jump 206
+    0xFF, 0xFF, // 208: not an instruction
+];
+
+fn traced(tracer: impl FnOnce(Tracer) -> Tracer) -> (Chip8, Shared) {
+    let out = Shared::default();
+    let chip8 = Chip8::new()
+        .with_tracer(tracer(Tracer::new(out.clone())))
+        .load_rom_bytes(&PROGRAM)
+        .unwrap();
+    (chip8, out)
+}
+
+#[test]
+fn every_instruction_is_logged_with_its_changes() {
+    let (mut chip8, out) = traced(|tracer| tracer);
+    chip8.run(4).unwrap();
+
+    let lines: Vec<String> = out
+        .lines()
+        .iter()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            "1 200 6005 v0 := 0x05 V0 00->05",
+            "2 202 70FF v0 += 0xFF V0 05->04",
+            "3 204 A300 i := 0x300 I 000->300",
+            "4 206 1206 jump 0x206",
+        ]
+    );
+    assert_eq!(chip8.take_tracer().unwrap().cycles(), 4);
+}
+
+#[test]
+fn range_filters_by_address() {
+    let (mut chip8, out) = traced(|tracer| tracer.with_range(0x202, 0x204));
+    chip8.run(10).unwrap();
+
+    let addresses: Vec<String> = out
+        .lines()
+        .iter()
+        .map(|line| line.split_whitespace().nth(1).unwrap().to_string())
+        .collect();
+    assert_eq!(addresses, ["202", "204"]);
+}
+
+#[test]
+fn ring_buffer_only_writes_on_error() {
+    let (mut chip8, out) = traced(|tracer| tracer.with_ring_buffer(2));
+    chip8.run(10).unwrap();
+    assert!(out.lines().is_empty());
+
+    // Straight into the bad opcode
+    let (mut chip8, out) = traced(|tracer| tracer.with_ring_buffer(2));
+    chip8.run(3).unwrap();
+    let mut jumped = PROGRAM;
+    jumped[6..8].copy_from_slice(&[0x12, 0x08]);
+    chip8.reload_rom(&jumped).unwrap();
+    assert!(chip8.run(10).is_err());
+
+    let lines = out.lines();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("jump 0x208"), "{}", lines[0]);
+    assert!(
+        lines[1].contains("error: unknown opcode 0xFFFF"),
+        "{}",
+        lines[1]
+    );
+}
+
+#[test]
+fn loading_a_state_keeps_the_tracer() {
+    let (mut chip8, out) = traced(|tracer| tracer);
+    let state = chip8.save_state();
+    chip8.run(2).unwrap();
+    chip8.load_state(&state).unwrap();
+    chip8.run(1).unwrap();
+
+    let lines = out.lines();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[2].trim_start().starts_with("3  200"), "{}", lines[2]);
+}
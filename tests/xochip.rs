@@ -0,0 +1,65 @@
+use rust_8::{Chip8, Chip8Error, Variant};
+
+fn xochip(rom: &[u8]) -> Chip8 {
+    Chip8::new()
+        .with_variant(Variant::XoChip)
+        .load_rom_bytes(rom)
+        .unwrap()
+}
+
+#[test]
+fn skips_jump_over_long_index() {
+    // skip if v0 == 0, over i := long 0xFFFF; landing on the operand would be an unknown opcode
+    let mut chip8 = xochip(&[0x30, 0x00, 0xF0, 0x00, 0xFF, 0xFF, 0x12, 0x06]);
+    chip8.run(3).unwrap();
+}
+
+#[test]
+fn long_index_reaches_all_64k() {
+    // i := long 0xFFFE; save v1; save v2
+    let mut chip8 = xochip(&[
+        0xF0, 0x00, 0xFF, 0xFE, 0xF1, 0x55, 0xF0, 0x00, 0xFF, 0xFE, 0xF2, 0x55,
+    ]);
+    chip8.run(3).unwrap();
+    assert!(matches!(
+        chip8.run(1),
+        Err(Chip8Error::MemoryOutOfBounds {
+            pc: 0x20A,
+            address: 0x10000
+        })
+    ));
+}
+
+#[test]
+fn xochip_opcodes_are_unknown_on_superchip() {
+    for rom in [
+        [0xF0, 0x00],
+        [0xF1, 0x01],
+        [0xF0, 0x02],
+        [0xF0, 0x3A],
+        [0x50, 0x12],
+        [0x00, 0xD1],
+    ] {
+        let mut chip8 = Chip8::new()
+            .with_variant(Variant::SuperChip)
+            .load_rom_bytes(&rom)
+            .unwrap();
+        assert!(matches!(
+            chip8.run(1),
+            Err(Chip8Error::UnknownOpcode { .. })
+        ));
+    }
+}
+
+#[test]
+fn audio_pattern_and_pitch() {
+    // i := 0x206; audio; v0 := 0x70; pitch := v0; then 16 bytes of pattern
+    let mut rom = vec![0xA2, 0x0A, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A, 0x12, 0x08];
+    rom.extend(0..16);
+    let mut chip8 = xochip(&rom);
+    chip8.run(4).unwrap();
+
+    let expected: Vec<u8> = (0..16).collect();
+    assert_eq!(&chip8.audio_pattern()[..], &expected[..]);
+    assert_eq!(chip8.pitch(), 0x70);
+}